@@ -0,0 +1,75 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::config::EndpointConfig;
+
+const USAGE: &str = "\
+Usage:
+  mockserver                                  Start the server (config from CONFIG_PATH)
+  mockserver import-openapi <spec> [<output>] Convert an OpenAPI 3 document to a config file
+  mockserver export-openapi [<config>] [<output>]
+                                              Render a config as an OpenAPI 3.1 document";
+
+#[derive(Serialize)]
+struct EndpointsFile<'a> {
+    endpoints: &'a [EndpointConfig],
+}
+
+/// Runs the command given on the command line, if any, and returns the
+/// process exit code. Without a command the server starts as usual.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let result = match command.as_str() {
+        "import-openapi" => import_openapi(rest),
+        "export-openapi" => export_openapi(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(format!("Unknown command: {}\n\n{}", other, USAGE).into()),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+fn import_openapi(args: &[String]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let [spec, output @ ..] = args else {
+        return Err(format!("import-openapi needs a spec file\n\n{}", USAGE).into());
+    };
+    let config = crate::openapi::import_openapi(Path::new(spec))?;
+    // Endpoints only, so the file can also be included from another config
+    let yaml = serde_yaml::to_string(&EndpointsFile {
+        endpoints: &config.endpoints,
+    })?;
+    write_output(output.first(), &yaml)?;
+    if let Some(output) = output.first() {
+        eprintln!("Wrote {} endpoints to {}", config.endpoints.len(), output);
+    }
+    Ok(())
+}
+
+fn export_openapi(args: &[String]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config_path = args.first().cloned().unwrap_or_else(crate::config_path);
+    let config = crate::config::load_config(&config_path)?;
+    let document = serde_json::to_string_pretty(&crate::openapi::export_openapi(&config))?;
+    write_output(args.get(1), &format!("{}\n", document))?;
+    Ok(())
+}
+
+/// Writes to the given file, or to stdout without one.
+fn write_output(output: Option<&String>, content: &str) -> std::io::Result<()> {
+    match output {
+        Some(path) => fs::write(path, content),
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
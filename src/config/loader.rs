@@ -1,26 +1,729 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use super::MockConfig;
+use base64::Engine;
+use serde_json::Value;
+
+use crate::response::{BodyTemplate, Template};
+
+use super::{
+    DelayConfig, EndpointConfig, GraphQLConfig, GrpcConfig, GrpcStatus, MockConfig, ProxyConfig,
+    ResponseConfig, ResponseStatus, SeedConfig, SequenceMode, SseConfig, SseRepeat,
+    ValidationConfig, WebSocketClose,
+};
+
+/// A merged config together with every file it was built from: config
+/// files first, then the files they reference (`bodyFile`, `$schema` files,
+/// GraphQL schemas and `.proto` files).
+pub struct LoadedConfig {
+    pub config: MockConfig,
+    pub files: Vec<PathBuf>,
+}
 
 pub fn load_config(path: &str) -> Result<MockConfig, Box<dyn std::error::Error + Send + Sync>> {
+    load_config_sources(path).map(|loaded| loaded.config)
+}
+
+/// Loads a config file, or every `.yaml`, `.yml` and `.json` file of a
+/// directory, following `include:` entries, and merges the result.
+pub fn load_config_sources(
+    path: &str,
+) -> Result<LoadedConfig, Box<dyn std::error::Error + Send + Sync>> {
     let path = Path::new(path);
     
     if !path.exists() {
         return Err(format!("Config file not found: {}", path.display()).into());
     }
 
-    let content = fs::read_to_string(path)?;
+    let roots = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && is_config_file(p))
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut merger = Merger::default();
+    for root in &roots {
+        merger.load_file(root)?;
+    }
+    let Merger {
+        config,
+        mut files,
+        referenced,
+        ..
+    } = merger;
+
+    validate_config(&config)?;
+
+    tracing::info!(
+        "Loaded {} endpoints from {} config file(s)",
+        config.endpoints.len(),
+        files.len()
+    );
+
+    for file in referenced {
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    Ok(LoadedConfig { config, files })
+}
+
+pub fn is_config_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml") | Some("yml") | Some("json")
+    )
+}
+
+#[derive(Default)]
+struct Merger {
+    config: MockConfig,
+    files: Vec<PathBuf>,
+    /// Files read on behalf of the config files, kept apart so one of them
+    /// is still merged if it is also a config file.
+    referenced: Vec<PathBuf>,
+    server_from: Option<PathBuf>,
+    routes: HashMap<(String, String, String), PathBuf>,
+    scenarios: HashMap<String, PathBuf>,
+}
+
+impl Merger {
+    fn load_file(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        // Each file is merged once, which also breaks include cycles
+        if self.files.contains(&canonical) {
+            return Ok(());
+        }
+        self.files.push(canonical.clone());
+
+        // A spec dropped into a config directory is served as it is
+        if crate::openapi::is_openapi_file(path) {
+            let imported = crate::openapi::import_openapi(path)?;
+            self.merge_imported(imported.endpoints, &canonical);
+            return Ok(());
+        }
+
+        let (mut file_config, declares_server) = parse_file(path)?;
+        let base_dir = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+        for response in file_config.endpoints.iter_mut().flat_map(|e| e.all_responses_mut()) {
+            let representation_files = response
+                .representations
+                .iter_mut()
+                .filter_map(|r| r.body_file.as_mut());
+            for file in response.body_file.iter_mut().chain(representation_files) {
+                *file = base_dir.join(&*file);
+                self.reference(file);
+            }
+
+            let bodies = std::iter::once(&mut response.body)
+                .chain(response.representations.iter_mut().filter_map(|r| r.body.as_mut()));
+            for body in bodies {
+                let mut value = body.value().clone();
+                let mut schema_files = Vec::new();
+                inline_schema_files(&mut value, &base_dir, &mut schema_files)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                if schema_files.is_empty() {
+                    continue;
+                }
+                *body = BodyTemplate::new(value)?;
+                for schema_file in &schema_files {
+                    self.reference(schema_file);
+                }
+            }
+        }
+        for graphql in file_config.endpoints.iter_mut().filter_map(|e| e.graphql.as_mut()) {
+            if let Some(schema) = &mut graphql.schema {
+                *schema = base_dir.join(&*schema);
+                self.reference(schema);
+            }
+        }
+        if let Some(grpc) = &mut file_config.grpc {
+            for path in grpc.protos.iter_mut().chain(grpc.import_paths.iter_mut()) {
+                *path = base_dir.join(&*path);
+            }
+            for proto in &grpc.protos {
+                self.reference(proto);
+            }
+        }
+        // Scenarios may be declared in another file, so references are only
+        // checked once everything is merged
+        validate_endpoints(&file_config).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if declares_server && self.server_from.is_none() {
+            self.config.server = file_config.server.clone();
+            self.server_from = Some(canonical.clone());
+        }
+        if self.config.proxy.is_none() {
+            self.config.proxy = file_config.proxy.clone();
+        }
+        if self.config.grpc.is_none() {
+            self.config.grpc = file_config.grpc.clone();
+        }
+
+        for (name, scenario) in file_config.scenarios {
+            if let Some(previous) = self.scenarios.get(&name) {
+                return Err(format!(
+                    "Duplicate scenario {} defined in {} and {}",
+                    name,
+                    previous.display(),
+                    canonical.display()
+                )
+                .into());
+            }
+            self.scenarios.insert(name.clone(), canonical.clone());
+            self.config.scenarios.insert(name, scenario);
+        }
+
+        for endpoint in file_config.endpoints {
+            let key = route_key(&endpoint);
+            if let Some(previous) = self.routes.get(&key) {
+                return Err(format!(
+                    "Duplicate endpoint {} {} defined in {} and {}",
+                    key.0,
+                    endpoint.path,
+                    previous.display(),
+                    canonical.display()
+                )
+                .into());
+            }
+            self.routes.insert(key, canonical.clone());
+            self.config.endpoints.push(endpoint);
+        }
+
+        // Operations of an OpenAPI spec fill in routes the file does not
+        // define itself
+        if let Some(spec) = &file_config.openapi {
+            let spec_path = base_dir.join(spec);
+            let imported = crate::openapi::import_openapi(&spec_path)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            if let Ok(spec_path) = spec_path.canonicalize() {
+                if !self.files.contains(&spec_path) {
+                    self.files.push(spec_path);
+                }
+            }
+            self.merge_imported(imported.endpoints, &canonical);
+        }
+
+        for pattern in &file_config.include {
+            for included in resolve_include(&base_dir, pattern)
+                .map_err(|e| format!("{}: {}", path.display(), e))?
+            {
+                self.load_file(&included)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Notes a file the config reads, so changes to it reload the config
+    /// too. Files that do not exist are reported by validation instead.
+    fn reference(&mut self, file: &Path) {
+        if let Ok(file) = file.canonicalize() {
+            if !self.referenced.contains(&file) {
+                self.referenced.push(file);
+            }
+        }
+    }
+
+    /// Adds endpoints imported from an OpenAPI spec, skipping routes that are
+    /// already defined.
+    fn merge_imported(&mut self, endpoints: Vec<EndpointConfig>, source: &Path) {
+        for endpoint in endpoints {
+            let key = route_key(&endpoint);
+            if self.routes.contains_key(&key) {
+                tracing::debug!("{} {} is already defined, skipping the spec's", key.0, endpoint.path);
+                continue;
+            }
+            self.routes.insert(key, source.to_path_buf());
+            self.config.endpoints.push(endpoint);
+        }
+    }
+}
+
+/// What makes two endpoints the same route: method (or `RESOURCE`), path
+/// shape, query matchers and scenario state.
+fn route_key(endpoint: &EndpointConfig) -> (String, String, String) {
+    let method = match endpoint.resource {
+        Some(_) => "RESOURCE".to_string(),
+        None => endpoint.method.to_uppercase(),
+    };
+    (
+        method,
+        route_shape(&endpoint.path),
+        format!(
+            "{}{}",
+            serde_json::to_string(&endpoint.query).unwrap_or_default(),
+            serde_json::to_string(&endpoint.scenario).unwrap_or_default()
+        ),
+    )
+}
+
+/// Names an endpoint's route for state kept across reloads, such as sequence
+/// counters. It is made of the same parts as [`route_key`], so adding,
+/// removing or moving other endpoints leaves it unchanged.
+pub fn route_name(endpoint: &EndpointConfig) -> String {
+    let (method, shape, _) = route_key(endpoint);
+    let mut name = format!("{} /{}", method, shape);
+    if !endpoint.query.is_empty() {
+        name.push_str(&format!(" ?{}", serde_json::to_string(&endpoint.query).unwrap_or_default()));
+    }
+    if let Some(scenario) = &endpoint.scenario {
+        name.push_str(&format!(" {}", serde_json::to_string(scenario).unwrap_or_default()));
+    }
+    name
+}
+
+/// Reads and deserializes a single file. Also reports whether the file sets
+/// `server:` itself, since the merged config keeps the first one declared.
+fn parse_file(path: &Path) -> Result<(MockConfig, bool), Box<dyn std::error::Error + Send + Sync>> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
     
     let config: MockConfig = match path.extension().and_then(|e| e.to_str()) {
-        Some("yaml") | Some("yml") => serde_yaml::from_str(&content)?,
-        Some("json") => serde_json::from_str(&content)?,
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content).map_err(|e| {
+            match e.location() {
+                Some(loc) => located(path, loc.line(), loc.column(), &e),
+                None => format!("{}: {}", path.display(), e),
+            }
+        })?,
+        Some("json") => serde_json::from_str(&content).map_err(|e| match e.line() {
+            0 => format!("{}: {}", path.display(), e),
+            line => located(path, line, e.column(), &e),
+        })?,
         _ => return Err("Unsupported config format. Use .yaml, .yml or .json".into()),
     };
 
-    tracing::info!("Loaded {} endpoints from config", config.endpoints.len());
-    
-    Ok(config)
+    let declares_server = serde_yaml::from_str::<serde_yaml::Value>(&content)
+        .map(|doc| doc.get("server").is_some())
+        .unwrap_or(false);
+
+    Ok((config, declares_server))
+}
+
+/// Formats a parse error as `file:line:column: message`. Both parsers end
+/// their messages with the same position, which is dropped there.
+fn located(path: &Path, line: usize, column: usize, error: &dyn std::fmt::Display) -> String {
+    let message = error.to_string();
+    let position = format!(" at line {} column {}", line, column);
+    let message = message.strip_suffix(&position).unwrap_or(&message);
+    format!("{}:{}:{}: {}", path.display(), line, column, message)
+}
+
+/// Replaces each `$schema: { $ref: "file" }` directive with the contents of
+/// the file, read relative to the config, and collects the files read.
+fn inline_schema_files(
+    value: &mut Value,
+    base_dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            if let Some(schema) = map.get_mut("$schema").filter(|s| s.is_object()) {
+                if let Some(file) = schema_file(schema) {
+                    let file = base_dir.join(file);
+                    let text = fs::read_to_string(&file)
+                        .map_err(|e| format!("$schema {}: {}", file.display(), e))?;
+                    *schema = serde_yaml::from_str(&text)
+                        .map_err(|e| format!("$schema {}: {}", file.display(), e))?;
+                    files.push(file.canonicalize().unwrap_or(file));
+                }
+                return Ok(());
+            }
+            for item in map.values_mut() {
+                inline_schema_files(item, base_dir, files)?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                inline_schema_files(item, base_dir, files)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The file a `$schema` directive refers to, if it is a `$ref` to one
+/// rather than to a `#` pointer or an inline schema.
+fn schema_file(schema: &Value) -> Option<&str> {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .filter(|target| !target.starts_with('#'))
+}
+
+/// Expands one `include:` entry, relative to the including file's directory.
+/// Glob patterns may match nothing; plain paths must exist.
+fn resolve_include(base_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let full = base_dir.join(pattern);
+    let full_str = full.to_string_lossy();
+
+    if !pattern.contains(['*', '?', '[']) {
+        if !full.exists() {
+            return Err(format!("included file not found: {}", full.display()));
+        }
+        return Ok(vec![full]);
+    }
+
+    let mut matches = Vec::new();
+    for entry in glob::glob(&full_str).map_err(|e| format!("invalid include \"{}\": {}", pattern, e))? {
+        let path = entry.map_err(|e| e.to_string())?;
+        if path.is_file() && is_config_file(&path) {
+            matches.push(path);
+        }
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Reduces a route to its shape so `/users/:id` and `/users/:userId` are
+/// recognised as the same route.
+fn route_shape(path: &str) -> String {
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| if s.starts_with(':') { ":" } else { s })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Checks the parts of a config that deserialize fine but can never serve a
+/// request, so a bad edit is rejected before it replaces a working config.
+pub fn validate_config(config: &MockConfig) -> Result<(), String> {
+    validate_endpoints(config)?;
+    validate_scenarios(config)
+}
+
+fn validate_endpoints(config: &MockConfig) -> Result<(), String> {
+    if let Some(proxy) = &config.proxy {
+        validate_proxy(proxy).map_err(|e| format!("proxy: {}", e))?;
+    }
+    if let Some(grpc) = &config.grpc {
+        validate_grpc(grpc).map_err(|e| format!("grpc: {}", e))?;
+    }
+
+    for (index, endpoint) in config.endpoints.iter().enumerate() {
+        let name = format!("endpoint #{} ({} {})", index + 1, endpoint.method, endpoint.path);
+
+        if !endpoint.path.starts_with('/') {
+            return Err(format!("{}: path must start with '/'", name));
+        }
+        if axum::http::Method::from_bytes(endpoint.method.as_bytes()).is_err() {
+            return Err(format!("{}: invalid HTTP method", name));
+        }
+        if let Some(delay) = &endpoint.delay {
+            validate_delay(delay).map_err(|e| format!("{}: {}", name, e))?;
+        }
+
+        if let Some(SeedConfig::PerParam { param }) = &endpoint.seed {
+            let in_path = endpoint
+                .path
+                .split('/')
+                .any(|segment| segment.strip_prefix(':') == Some(param.as_str()));
+            let resource_id = endpoint.resource.as_ref().is_some_and(|r| &r.id_field == param);
+            if !in_path && !resource_id {
+                return Err(format!("{}: seed param {} is not a path parameter", name, param));
+            }
+        }
+
+        let conditions = endpoint
+            .conditions
+            .iter()
+            .map(|c| &c.condition)
+            .chain(endpoint.websocket.iter().flat_map(|ws| ws.replies.iter().map(|r| &r.condition)));
+        for condition in conditions {
+            condition.validate().map_err(|e| format!("{}: {}", name, e))?;
+        }
+
+        if let Some(proxy) = &endpoint.proxy {
+            validate_proxy(proxy).map_err(|e| format!("{}: proxy: {}", name, e))?;
+        }
+
+        if let Some(Err(e)) = endpoint.validation.as_ref().and_then(ValidationConfig::body_schema) {
+            return Err(format!("{}: validation: invalid body schema: {}", name, e));
+        }
+
+        if let Some(graphql) = &endpoint.graphql {
+            validate_graphql(graphql).map_err(|e| format!("{}: graphql: {}", name, e))?;
+        }
+
+        if let Some(script) = &endpoint.websocket {
+            if script.periodic.iter().any(|periodic| periodic.interval == 0) {
+                return Err(format!("{}: periodic messages need a positive interval", name));
+            }
+            let closes = script
+                .close
+                .iter()
+                .chain(script.replies.iter().filter_map(|reply| reply.close.as_ref()));
+            for close in closes {
+                validate_close(close).map_err(|e| format!("{}: close: {}", name, e))?;
+            }
+            for delay in script.replies.iter().filter_map(|reply| reply.delay.as_ref()) {
+                validate_delay(delay).map_err(|e| format!("{}: reply: {}", name, e))?;
+            }
+        }
+
+        let random = endpoint
+            .sequence
+            .as_ref()
+            .is_some_and(|s| s.mode == SequenceMode::Random);
+        if random && endpoint.responses.iter().all(|e| e.weight == 0) {
+            return Err(format!("{}: random responses need a positive weight", name));
+        }
+
+        for response in endpoint.all_responses() {
+            validate_body(response).map_err(|e| format!("{}: {}", name, e))?;
+        }
+
+        let statuses = endpoint
+            .all_responses()
+            .map(|response| &response.status)
+            .filter_map(|status| match status {
+                ResponseStatus::Code(code) => Some(*code),
+                ResponseStatus::Template(_) => None,
+            })
+            .chain(endpoint.validation.iter().map(|v| v.status));
+        for status in statuses {
+            if axum::http::StatusCode::from_u16(status).is_err() {
+                return Err(format!("{}: invalid status code {}", name, status));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_body(response: &ResponseConfig) -> Result<(), String> {
+    if let Some(sse) = &response.sse {
+        validate_sse(sse)?;
+        if response.text.is_some()
+            || response.body_file.is_some()
+            || response.base64.is_some()
+            || !response.representations.is_empty()
+        {
+            return Err("sse cannot be combined with text, bodyFile, base64 or representations"
+                .to_string());
+        }
+    }
+    validate_raw_body(&response.text, &response.body_file, &response.base64)?;
+    validate_schema_directives(response.body.value())?;
+    for representation in &response.representations {
+        if let Some(body) = &representation.body {
+            validate_schema_directives(body.value())
+                .map_err(|e| format!("representation {}: {}", representation.media_type, e))?;
+        }
+        let media_type = &representation.media_type;
+        let has_raw_body = representation.text.is_some()
+            || representation.body_file.is_some()
+            || representation.base64.is_some();
+        if !has_raw_body && !crate::response::is_convertible(media_type) {
+            return Err(format!(
+                "representation {} needs a text, bodyFile or base64 body; \
+                 only JSON, XML, CSV, YAML and plain text are converted from body",
+                media_type
+            ));
+        }
+        validate_raw_body(&representation.text, &representation.body_file, &representation.base64)
+            .map_err(|e| format!("representation {}: {}", media_type, e))?;
+    }
+    Ok(())
+}
+
+/// The schema must load and parse, and resolvers must name fields it has.
+fn validate_graphql(graphql: &GraphQLConfig) -> Result<(), String> {
+    let schema = graphql.parsed_schema()?;
+    for (type_name, fields) in &graphql.resolvers {
+        let known = schema
+            .fields(type_name)
+            .ok_or_else(|| format!("resolvers: {} is not an object or interface type", type_name))?;
+        if let Some(field) = fields.keys().find(|field| !known.contains_key(*field)) {
+            return Err(format!("resolvers: type {} has no field {}", type_name, field));
+        }
+    }
+    Ok(())
+}
+
+/// The protos must parse, and every mocked method must be one of their
+/// services' methods with a known status code.
+fn validate_grpc(grpc: &GrpcConfig) -> Result<(), String> {
+    let descriptors = crate::grpc::load_descriptors(grpc)?;
+    for mocked in &grpc.methods {
+        let name = mocked.method.trim_start_matches('/');
+        let (service, method) = name
+            .split_once('/')
+            .ok_or_else(|| format!("method {} is not of the form package.Service/Method", name))?;
+        let known = descriptors
+            .get_service_by_name(service)
+            .ok_or_else(|| format!("unknown service {}", service))?
+            .methods()
+            .any(|m| m.name() == method);
+        if !known {
+            return Err(format!("service {} has no method {}", service, method));
+        }
+        if let Some(delay) = &mocked.delay {
+            validate_delay(delay).map_err(|e| format!("{}: {}", name, e))?;
+        }
+        let replies = std::iter::once(&mocked.reply).chain(mocked.conditions.iter().map(|c| &c.reply));
+        for reply in replies {
+            match &reply.status {
+                GrpcStatus::Code(code) if reply.status.code().is_none() => {
+                    return Err(format!("{}: unknown status code {}", name, code));
+                }
+                GrpcStatus::Name(status) if reply.status.code().is_none() => {
+                    return Err(format!("{}: unknown status {}", name, status));
+                }
+                _ => {}
+            }
+            for delay in reply.stream.iter().filter_map(|message| message.delay.as_ref()) {
+                validate_delay(delay).map_err(|e| format!("{}: stream: {}", name, e))?;
+            }
+        }
+        for condition in mocked.conditions.iter().map(|c| &c.condition) {
+            condition.validate().map_err(|e| format!("{}: {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Every `$schema` directive must hold a schema that compiles. File
+/// references are inlined when a config file is loaded, so one left over
+/// came from a config posted over HTTP.
+fn validate_schema_directives(value: &Value) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            if let Some(schema) = map.get("$schema").filter(|s| s.is_object()) {
+                if let Some(file) = schema_file(schema) {
+                    return Err(format!(
+                        "$schema file {} can only be referenced from a config file",
+                        file
+                    ));
+                }
+                return jsonschema::JSONSchema::compile(schema)
+                    .map(|_| ())
+                    .map_err(|e| format!("invalid $schema: {}", e));
+            }
+            map.values().try_for_each(validate_schema_directives)
+        }
+        Value::Array(items) => items.iter().try_for_each(validate_schema_directives),
+        _ => Ok(()),
+    }
+}
+
+/// Only codes an endpoint may send: 1004-1006 and 1015 are reserved for
+/// what clients report themselves, and 1016-2999 are unassigned. The reason
+/// must fit in a control frame.
+fn validate_close(close: &WebSocketClose) -> Result<(), String> {
+    if !matches!(close.code, 1000..=1003 | 1007..=1014 | 3000..=4999) {
+        return Err(format!("code {} cannot be sent by an endpoint", close.code));
+    }
+    if close.reason.len() > 123 {
+        return Err("reason is longer than 123 bytes".to_string());
+    }
+    Ok(())
+}
+
+fn validate_delay(delay: &DelayConfig) -> Result<(), String> {
+    match delay {
+        DelayConfig::Range { min, max, .. } if min > max => {
+            Err(format!("delay min ({}) is greater than max ({})", min, max))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_sse(sse: &SseConfig) -> Result<(), String> {
+    if sse.events.is_empty() {
+        return Err("sse needs at least one event".to_string());
+    }
+    for (index, event) in sse.events.iter().enumerate() {
+        if let Some(delay) = &event.delay {
+            validate_delay(delay).map_err(|e| format!("sse event #{}: {}", index + 1, e))?;
+        }
+    }
+    let delayed = sse.events.iter().any(|event| match &event.delay {
+        Some(DelayConfig::Fixed(ms)) => *ms > 0,
+        Some(DelayConfig::Range { max, .. }) => *max > 0,
+        None => false,
+    });
+    if matches!(sse.repeat, SseRepeat::Forever(_)) && !delayed {
+        return Err("an infinite sse stream needs a delay on at least one event".to_string());
+    }
+    Ok(())
+}
+
+fn validate_raw_body(
+    text: &Option<Template>,
+    body_file: &Option<PathBuf>,
+    base64: &Option<String>,
+) -> Result<(), String> {
+    if [text.is_some(), body_file.is_some(), base64.is_some()]
+        .iter()
+        .filter(|set| **set)
+        .count()
+        > 1
+    {
+        return Err("only one of text, bodyFile and base64 can be set".to_string());
+    }
+    if let Some(file) = body_file {
+        if !file.is_file() {
+            return Err(format!("bodyFile {} not found", file.display()));
+        }
+    }
+    if let Some(encoded) = base64 {
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("invalid base64 body: {}", e))?;
+    }
+    Ok(())
+}
+
+fn validate_proxy(proxy: &ProxyConfig) -> Result<(), String> {
+    let url = proxy.url();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!("\"{}\" is not an http(s) URL", url));
+    }
+    Ok(())
+}
+
+fn validate_scenarios(config: &MockConfig) -> Result<(), String> {
+    for (name, scenario) in &config.scenarios {
+        if !scenario.states.is_empty() && !scenario.states.contains(&scenario.initial) {
+            return Err(format!(
+                "scenario {}: initial state \"{}\" is not one of its states",
+                name, scenario.initial
+            ));
+        }
+    }
+
+    for (index, endpoint) in config.endpoints.iter().enumerate() {
+        let Some(step) = &endpoint.scenario else {
+            continue;
+        };
+        let name = format!("endpoint #{} ({} {})", index + 1, endpoint.method, endpoint.path);
+        let scenario = config
+            .scenarios
+            .get(&step.name)
+            .ok_or_else(|| format!("{}: unknown scenario \"{}\"", name, step.name))?;
+        if scenario.states.is_empty() {
+            continue;
+        }
+        for state in step.state.iter().chain(step.transition.iter()) {
+            if !scenario.states.contains(state) {
+                return Err(format!(
+                    "{}: scenario {} has no state \"{}\"",
+                    name, step.name, state
+                ));
+            }
+        }
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -44,4 +747,279 @@ endpoints:
         assert_eq!(config.server.port, 8080);
         assert_eq!(config.endpoints.len(), 1);
     }
+
+    #[test]
+    fn test_parse_error_reports_line_and_column() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("endpoints.yaml");
+        fs::write(&path, "endpoints:\n  - path: \"/test\"\n    response: [\n").unwrap();
+
+        let err = load_config(path.to_str().unwrap()).unwrap_err().to_string();
+        assert!(err.starts_with(&format!("{}:3:15:", path.display())), "{}", err);
+        assert!(!err.contains("at line"), "{}", err);
+
+        let path = dir.path().join("endpoints.json");
+        fs::write(&path, "{\"endpoints\": [\n  {\"path\": 12}\n]}").unwrap();
+        let err = load_config(path.to_str().unwrap()).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            format!("{}:2:13: invalid type: integer `12`, expected a string", path.display())
+        );
+    }
+
+    #[test]
+    fn test_directory_with_includes_is_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("shared")).unwrap();
+        fs::write(
+            dir.path().join("a-main.yaml"),
+            "server: { port: 9000 }\ninclude: [\"shared/*.yaml\"]\nendpoints:\n  - path: /a\n    response: { body: null }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("b-users.json"),
+            r#"{ "server": { "port": 9999 }, "endpoints": [{ "path": "/users/:id", "response": { "body": null } }] }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("shared/health.yaml"),
+            "endpoints:\n  - path: /health\n    response: { body: null }\n",
+        )
+        .unwrap();
+
+        let loaded = load_config_sources(dir.path().to_str().unwrap()).unwrap();
+        let paths: Vec<&str> = loaded.config.endpoints.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/a", "/health", "/users/:id"]);
+        assert_eq!(loaded.config.server.port, 9000);
+        assert_eq!(loaded.files.len(), 3);
+    }
+
+    #[test]
+    fn test_openapi_source_fills_in_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("spec.yaml"),
+            r#"
+openapi: 3.1.0
+info: { title: Users, version: "1" }
+paths:
+  /users:
+    get:
+      responses:
+        "200": { description: ok, content: { application/json: { example: [] } } }
+  /users/{id}:
+    get:
+      responses:
+        "200": { description: ok, content: { application/json: { example: { id: 1 } } } }
+"#,
+        )
+        .unwrap();
+        let config_path = dir.path().join("endpoints.yaml");
+        fs::write(
+            &config_path,
+            "openapi: spec.yaml\nendpoints:\n  - path: /users\n    response: { body: [\"custom\"] }\n",
+        )
+        .unwrap();
+
+        let loaded = load_config_sources(config_path.to_str().unwrap()).unwrap();
+        let paths: Vec<&str> = loaded.config.endpoints.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/users", "/users/:id"]);
+        assert_eq!(loaded.config.endpoints[0].response.body.value()[0], "custom");
+        assert_eq!(loaded.files.len(), 2);
+
+        // The spec alone in a directory is served too
+        fs::remove_file(&config_path).unwrap();
+        let loaded = load_config_sources(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(loaded.config.endpoints.len(), 2);
+    }
+
+    #[test]
+    fn test_body_file_is_relative_to_its_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::write(dir.path().join("files/report.csv"), "id,name\n1,Ana\n").unwrap();
+        fs::write(
+            dir.path().join("endpoints.yaml"),
+            "endpoints:\n  - path: /report\n    response: { bodyFile: files/report.csv }\n",
+        )
+        .unwrap();
+
+        let loaded = load_config_sources(dir.path().to_str().unwrap()).unwrap();
+        let file = loaded.config.endpoints[0].response.body_file.as_ref().unwrap();
+        assert_eq!(fs::read_to_string(file).unwrap(), "id,name\n1,Ana\n");
+
+        fs::remove_file(dir.path().join("files/report.csv")).unwrap();
+        let error = load_config(dir.path().to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_schema_files_are_inlined_and_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("schemas")).unwrap();
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["id", "email"],
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "email": { "type": "string", "format": "email" }
+            }
+        });
+        fs::write(dir.path().join("schemas/user.json"), schema.to_string()).unwrap();
+        fs::write(
+            dir.path().join("endpoints.yaml"),
+            "endpoints:\n  - path: /user\n    response:\n      body:\n        user: { $schema: { $ref: schemas/user.json } }\n",
+        )
+        .unwrap();
+
+        let loaded = load_config_sources(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(loaded.files.len(), 2);
+        let body = &loaded.config.endpoints[0].response.body;
+        assert_eq!(body.value()["user"]["$schema"], schema);
+        let generated = crate::response::generate_response_body(body, &Default::default());
+        assert!(jsonschema::is_valid(&schema, &generated["user"]));
+
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/bad"
+    response: { body: { $schema: { type: 12 } } }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config).unwrap_err().contains("invalid $schema"));
+    }
+
+    #[test]
+    fn test_graphql_schema_is_parsed_at_load() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("schema.graphql"), "type Query { hello: String }\n").unwrap();
+        fs::write(
+            dir.path().join("endpoints.yaml"),
+            "endpoints:\n  - path: /graphql\n    method: POST\n    graphql: { schema: schema.graphql }\n    response: { body: null }\n",
+        )
+        .unwrap();
+
+        let config = load_config(dir.path().to_str().unwrap()).unwrap();
+        fs::remove_file(dir.path().join("schema.graphql")).unwrap();
+        let graphql = config.endpoints[0].graphql.as_ref().unwrap();
+        assert_eq!(graphql.parsed_schema().unwrap().query, "Query");
+    }
+
+    #[test]
+    fn test_duplicate_routes_name_both_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("one.yaml"),
+            "endpoints:\n  - path: /users/:id\n    response: { body: null }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("two.yaml"),
+            "endpoints:\n  - path: /users/:userId\n    method: get\n    response: { body: null }\n",
+        )
+        .unwrap();
+
+        let err = load_config(dir.path().to_str().unwrap()).unwrap_err().to_string();
+        assert!(err.contains("Duplicate endpoint GET /users/:userId"), "{}", err);
+        assert!(err.contains("one.yaml") && err.contains("two.yaml"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_endpoints() {
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/test"
+    response: { status: 1200, body: null }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config).unwrap_err().contains("invalid status code"));
+
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/test"
+    method: POST
+    validation: { body: { type: 12 } }
+    response: { body: null }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config).unwrap_err().contains("invalid body schema"));
+
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/test"
+    conditions:
+      - if: { not: { body_feild: "role", equals: "admin" } }
+        response: { status: 403, body: null }
+    response: { body: null }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config).unwrap_err().contains("is a key misspelled?"));
+
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/ws"
+    websocket: { close: { code: 1006, after: 10 } }
+    response: { body: null }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config).unwrap_err().contains("code 1006 cannot be sent"));
+
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/ws"
+    websocket:
+      replies:
+        - if: { body_field: "type", equals: "ping" }
+          send: ["pong"]
+          delay: { min: 200, max: 50 }
+    response: { body: null }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config)
+            .unwrap_err()
+            .contains("reply: delay min (200) is greater than max (50)"));
+
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/events"
+    response:
+      sse:
+        events:
+          - data: "tick"
+            delay: { min: 500, max: 100 }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config)
+            .unwrap_err()
+            .contains("sse event #1: delay min (500) is greater than max (100)"));
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_scenario_states() {
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+scenarios:
+  checkout: { initial: "cart", states: ["cart", "paid"] }
+endpoints:
+  - path: "/pay"
+    method: POST
+    scenario: { name: "checkout", state: "cart", transition: "shipped" }
+"#,
+        )
+        .unwrap();
+        assert!(validate_config(&config).unwrap_err().contains("has no state \"shipped\""));
+    }
 }
@@ -1,11 +1,26 @@
 mod loader;
+mod pattern;
+mod recorder;
+mod scalar;
+mod watcher;
 
 pub use loader::*;
+pub use pattern::*;
+pub use recorder::*;
+pub use watcher::*;
 
+use axum::http::StatusCode;
+use jsonschema::JSONSchema;
 use parking_lot::RwLock;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::graphql::Schema;
+use crate::request::RequestContext;
+use crate::response::{BodyTemplate, Template, ValuePath};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
@@ -13,6 +28,13 @@ pub struct ServerConfig {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// How many requests `/_requests` keeps. 0 turns the journal off.
+    #[serde(default = "default_journal_limit")]
+    pub journal_limit: usize,
+    /// Seeds fake values, random delays and random sequences on every
+    /// endpoint, so responses are the same from run to run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 fn default_host() -> String {
@@ -23,46 +45,469 @@ fn default_port() -> u16 {
     3000
 }
 
+fn default_journal_limit() -> usize {
+    1000
+}
+
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
             host: default_host(),
             port: default_port(),
+            journal_limit: default_journal_limit(),
+            seed: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EndpointConfig {
     pub path: String,
     #[serde(default = "default_method")]
     pub method: String,
-    pub response: ResponseConfig,
+    /// Query values a request must carry for this endpoint to match.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub query: BTreeMap<String, ValueMatch>,
     #[serde(default)]
+    pub response: ResponseConfig,
+    /// Responses served in turn instead of `response`, as set by `sequence`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub responses: Vec<SequenceEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<SequenceConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub delay: Option<DelayConfig>,
-    #[serde(default)]
+    /// Seeds this endpoint's random values, overriding `server.seed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<SeedConfig>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub timeout: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub validation: Option<ValidationConfig>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub conditions: Vec<ConditionConfig>,
+    /// Serves a stateful CRUD collection at `path` and `path/:id` instead of
+    /// a fixed response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<ResourceConfig>,
+    /// Forwards matching requests to a real upstream instead of answering
+    /// from `response`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// Upgrades the connection to a WebSocket and runs this script on it
+    /// instead of sending a response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub websocket: Option<WebSocketConfig>,
+    /// Answers GraphQL queries against a schema instead of sending
+    /// `response`. Matches both GET and POST requests to `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<GraphQLConfig>,
+    /// Ties the endpoint to a scenario state machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<ScenarioStep>,
+}
+
+impl EndpointConfig {
+    /// Every response the endpoint can answer with: `response`, the
+    /// sequence's `responses` and the conditions' responses.
+    pub fn all_responses(&self) -> impl Iterator<Item = &ResponseConfig> {
+        std::iter::once(&self.response)
+            .chain(self.responses.iter().map(|e| &e.response))
+            .chain(self.conditions.iter().map(|c| &c.response))
+    }
+
+    pub fn all_responses_mut(&mut self) -> impl Iterator<Item = &mut ResponseConfig> {
+        std::iter::once(&mut self.response)
+            .chain(self.responses.iter_mut().map(|e| &mut e.response))
+            .chain(self.conditions.iter_mut().map(|c| &mut c.response))
+    }
+}
+
+/// An endpoint's part in a scenario: it only matches while the scenario is
+/// in `state` (any state when unset) and moves it to `transition` after
+/// responding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioConfig {
+    pub initial: String,
+    /// Known states. When listed, endpoints may only use these.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub states: Vec<String>,
 }
 
 fn default_method() -> String {
     "GET".to_string()
 }
 
+/// A response. The body is JSON (`body`) unless one of `text`, `bodyFile`
+/// or `base64` is set.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseConfig {
     #[serde(default = "default_status")]
-    pub status: u16,
+    pub status: ResponseStatus,
+    #[serde(default)]
+    pub headers: HashMap<String, Template>,
+    #[serde(default)]
+    pub body: BodyTemplate,
+    /// A raw text body such as XML, HTML or CSV; may hold `{{ }}` placeholders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<Template>,
+    /// A file served as the body, relative to the config file that names it.
+    #[serde(
+        default,
+        rename = "bodyFile",
+        alias = "body_file",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub body_file: Option<PathBuf>,
+    /// A binary body, base64-encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+    /// Content-Type of the body. Defaults to JSON for `body`, plain text for
+    /// `text`, the file extension's type for `bodyFile` and
+    /// `application/octet-stream` for `base64`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Media types the response can be served as, picked by the request's
+    /// `Accept` header. The first one is the default.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub representations: Vec<Representation>,
+    /// Streams Server-Sent Events instead of sending a body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sse: Option<SseConfig>,
+}
+
+/// One media type a response can be served as. Without a body of its own,
+/// the response's JSON `body` is converted to it (JSON, XML, CSV, YAML or
+/// plain text).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Representation {
+    #[serde(rename = "type")]
+    pub media_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<BodyTemplate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<Template>,
+    #[serde(
+        default,
+        rename = "bodyFile",
+        alias = "body_file",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub body_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+}
+
+fn default_status() -> ResponseStatus {
+    ResponseStatus::Code(200)
+}
+
+impl Default for ResponseConfig {
+    fn default() -> Self {
+        Self {
+            status: default_status(),
+            headers: HashMap::new(),
+            body: BodyTemplate::default(),
+            text: None,
+            body_file: None,
+            base64: None,
+            content_type: None,
+            representations: Vec::new(),
+            sse: None,
+        }
+    }
+}
+
+/// A Server-Sent Events stream: the events are sent in order, `repeat`
+/// times or forever.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SseConfig {
+    pub events: Vec<SseEvent>,
+    #[serde(default)]
+    pub repeat: SseRepeat,
+    /// Sends a comment line whenever the stream has been idle this long.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<SseKeepAlive>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SseEvent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<Template>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Template>,
+    /// Sent as is when it renders to a string, as JSON otherwise.
+    #[serde(default)]
+    pub data: BodyTemplate,
+    /// Wait before sending the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<DelayConfig>,
+}
+
+/// How many times an SSE stream goes through its events: a count or
+/// `infinite`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SseRepeat {
+    Times(u32),
+    Forever(SseForever),
+}
+
+impl Default for SseRepeat {
+    fn default() -> Self {
+        SseRepeat::Times(1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SseForever {
+    Infinite,
+}
+
+/// Keep-alive comments: an interval in milliseconds, or an interval plus
+/// the comment text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SseKeepAlive {
+    Interval(u64),
+    Comment { interval: u64, text: String },
+}
+
+/// A GraphQL endpoint. Query results follow the selection set: fields
+/// with a resolver get its rendered value, fields the parent value already
+/// holds keep it, and everything else is faked from its type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphQLConfig {
+    /// An SDL file, relative to the config file that names it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<PathBuf>,
+    /// The SDL itself, instead of `schema`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sdl: Option<String>,
+    /// Body templates by type and field name. Field arguments are available
+    /// as `params`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resolvers: BTreeMap<String, BTreeMap<String, BodyTemplate>>,
+    /// How many items faked lists get.
+    #[serde(default = "default_list_length")]
+    pub list_length: usize,
+    /// The schema once parsed, shared by every request.
+    #[serde(skip)]
+    parsed: OnceLock<Arc<Schema>>,
+}
+
+fn default_list_length() -> usize {
+    3
+}
+
+impl GraphQLConfig {
+    /// Reads the schema's SDL from `schema` or `sdl`.
+    pub fn load_sdl(&self) -> Result<String, String> {
+        match (&self.schema, &self.sdl) {
+            (Some(file), None) => {
+                std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))
+            }
+            (None, Some(sdl)) => Ok(sdl.clone()),
+            _ => Err("exactly one of schema and sdl must be set".to_string()),
+        }
+    }
+
+    /// The parsed schema. It is loaded the first time, which is when the
+    /// config is validated, and kept for as long as the config is.
+    pub fn parsed_schema(&self) -> Result<Arc<Schema>, String> {
+        if let Some(schema) = self.parsed.get() {
+            return Ok(schema.clone());
+        }
+        let schema = Arc::new(Schema::parse(&self.load_sdl()?)?);
+        Ok(self.parsed.get_or_init(|| schema).clone())
+    }
+}
+
+/// What a WebSocket endpoint does once connected. Messages are body
+/// templates: strings are sent as they render, anything else as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    /// Sent as soon as the connection is open.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_connect: Vec<BodyTemplate>,
+    /// Answers to incoming messages. The first reply whose condition matches
+    /// is used; the message is the request body the condition sees.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<WebSocketReply>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub periodic: Vec<PeriodicMessage>,
+    /// Closes the connection `after` milliseconds, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close: Option<WebSocketClose>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketReply {
+    #[serde(rename = "if")]
+    pub condition: ConditionCheck,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub send: Vec<BodyTemplate>,
+    /// Wait before replying.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<DelayConfig>,
+    /// Closes the connection after replying (`after` milliseconds later).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close: Option<WebSocketClose>,
+}
+
+/// A message sent every `interval` milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodicMessage {
+    pub interval: u64,
+    pub send: BodyTemplate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketClose {
+    #[serde(default = "default_close_code")]
+    pub code: u16,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<u64>,
+}
+
+fn default_close_code() -> u16 {
+    1000
+}
+
+/// One of an endpoint's `responses`. `weight` only matters in random mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceEntry {
+    #[serde(flatten)]
+    pub response: ResponseConfig,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// How an endpoint walks through its `responses`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SequenceConfig {
+    #[serde(default)]
+    pub mode: SequenceMode,
+    /// Keeps a separate position for each client instead of one per endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_client: Option<ClientKey>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SequenceMode {
+    /// Serve the responses in order, then keep serving the last one.
+    #[default]
+    Sequential,
+    /// Serve the responses in order, then start over.
+    Cycle,
+    /// Pick a response at random, proportionally to its weight.
+    Random,
+}
+
+/// How an endpoint seeds its random values: a fixed seed, or
+/// `{ param: id }` for values that stay the same per value of a path
+/// parameter (`/users/42` always gets the same fake user).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SeedConfig {
+    Fixed(u64),
+    PerParam { param: String },
+}
+
+/// What identifies a client: `ip` or `{ header: X-Client-Id }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ClientKey {
+    Header { header: String },
+    Address(ClientAddress),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAddress {
+    Ip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceConfig {
+    /// Field that identifies an item in the collection.
+    #[serde(default = "default_id_field")]
+    pub id_field: String,
+    /// Initial items: a literal array or a template such as `$array`,
+    /// rendered once when the collection is first used.
     #[serde(default)]
-    pub headers: HashMap<String, String>,
-    pub body: serde_json::Value,
+    pub seed: BodyTemplate,
 }
 
-fn default_status() -> u16 {
-    200
+fn default_id_field() -> String {
+    "id".to_string()
+}
+
+/// An upstream to forward requests to: a base URL, or a base URL plus a
+/// file that every upstream exchange is recorded to as a new endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProxyConfig {
+    Url(String),
+    Target {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        record: Option<PathBuf>,
+    },
+}
+
+impl ProxyConfig {
+    pub fn url(&self) -> &str {
+        match self {
+            ProxyConfig::Url(url) | ProxyConfig::Target { url, .. } => url,
+        }
+    }
+
+    pub fn record(&self) -> Option<&Path> {
+        match self {
+            ProxyConfig::Url(_) => None,
+            ProxyConfig::Target { record, .. } => record.as_deref(),
+        }
+    }
+}
+
+/// A status code, either fixed or rendered from a template such as
+/// `"{{query.status | default '200'}}"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResponseStatus {
+    Code(u16),
+    Template(Template),
+}
+
+impl ResponseStatus {
+    /// Resolves the status for a request. Templates that do not render to a
+    /// valid status code fall back to 500.
+    pub fn resolve(&self, ctx: &RequestContext) -> StatusCode {
+        let code = match self {
+            ResponseStatus::Code(code) => Some(*code),
+            ResponseStatus::Template(template) => template.render(ctx).trim().parse().ok(),
+        };
+        code.and_then(|c| StatusCode::from_u16(c).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,13 +529,51 @@ pub struct ValidationConfig {
     #[serde(default)]
     pub body: Option<serde_json::Value>,
     #[serde(default)]
-    pub headers: HashMap<String, String>,
+    pub headers: HashMap<String, Pattern>,
+    #[serde(default = "default_validation_status")]
+    pub status: u16,
+    /// The `body` schema once compiled, shared by every request.
+    #[serde(skip)]
+    pub(crate) compiled_body: OnceLock<Arc<JSONSchema>>,
+}
+
+fn default_validation_status() -> u16 {
+    400
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            params: HashMap::new(),
+            body: None,
+            headers: HashMap::new(),
+            status: default_validation_status(),
+            compiled_body: OnceLock::new(),
+        }
+    }
+}
+
+impl ValidationConfig {
+    /// The compiled `body` schema, if there is one. It is compiled the first
+    /// time, which is when the config is validated, and kept for as long as
+    /// the config is.
+    pub fn body_schema(&self) -> Option<Result<Arc<JSONSchema>, String>> {
+        let schema = self.body.as_ref()?;
+        if let Some(compiled) = self.compiled_body.get() {
+            return Some(Ok(compiled.clone()));
+        }
+        Some(
+            JSONSchema::compile(schema)
+                .map(|compiled| self.compiled_body.get_or_init(|| Arc::new(compiled)).clone())
+                .map_err(|e| e.to_string()),
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParamValidation {
     #[serde(default)]
-    pub pattern: Option<String>,
+    pub pattern: Option<Pattern>,
     #[serde(default)]
     pub required: bool,
 }
@@ -102,52 +585,292 @@ pub struct ConditionConfig {
     pub response: ResponseConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ConditionCheck {
-    #[serde(default)]
+/// A predicate tree. `all`, `any` and `not` nodes combine leaves; a leaf
+/// names one or more request subjects and the comparisons they must pass.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum ConditionCheck {
+    All { all: Vec<ConditionCheck> },
+    Any { any: Vec<ConditionCheck> },
+    Not { not: Box<ConditionCheck> },
+    Predicate(Box<Predicate>),
+}
+
+impl<'de> Deserialize<'de> for ConditionCheck {
+    /// Picks the variant from the node's key before deserializing it, so an
+    /// error inside it (a bad `matches` regex, say) is reported as it is
+    /// rather than as "data did not match any variant".
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        let node = value.as_object().and_then(|map| {
+            let key = ["all", "any", "not"].into_iter().find(|key| map.contains_key(*key))?;
+            Some((key, map.len()))
+        });
+        let check = match node {
+            Some((key, len)) if len > 1 => {
+                return Err(D::Error::custom(format!(
+                    "\"{}\" cannot be combined with other keys",
+                    key
+                )))
+            }
+            Some(("all", _)) => serde_json::from_value(value["all"].take())
+                .map(|all| ConditionCheck::All { all }),
+            Some(("any", _)) => serde_json::from_value(value["any"].take())
+                .map(|any| ConditionCheck::Any { any }),
+            Some(_) => serde_json::from_value(value["not"].take())
+                .map(|not| ConditionCheck::Not { not }),
+            None => serde_json::from_value(value).map(ConditionCheck::Predicate),
+        };
+        check.map_err(D::Error::custom)
+    }
+}
+
+impl ConditionCheck {
+    /// Fails on a leaf without a subject. A misspelled key such as `alll:`
+    /// or `body_feild:` still deserializes, as a leaf that never matches.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ConditionCheck::All { all: checks } | ConditionCheck::Any { any: checks } => {
+                checks.iter().try_for_each(ConditionCheck::validate)
+            }
+            ConditionCheck::Not { not } => not.validate(),
+            ConditionCheck::Predicate(predicate) if !predicate.has_subject() => Err(
+                "condition names no param, query, header, cookie, body_field, method or path \
+                 (is a key misspelled?)"
+                    .to_string(),
+            ),
+            ConditionCheck::Predicate(_) => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Predicate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub param: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<QuerySubject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub header: Option<String>,
-    #[serde(default)]
-    pub body_field: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookie: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_field: Option<ValuePath>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<ValueMatch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<ValueMatch>,
+    #[serde(flatten)]
+    pub compare: Comparison,
+}
+
+impl Predicate {
+    fn has_subject(&self) -> bool {
+        self.param.is_some()
+            || self.query.is_some()
+            || self.header.is_some()
+            || self.cookie.is_some()
+            || self.body_field.is_some()
+            || self.method.is_some()
+            || self.path.is_some()
+    }
+}
+
+/// A condition's `query` is either one key compared with the leaf's
+/// operators, or a section matching several keys at once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum QuerySubject {
+    Key(String),
+    Section(BTreeMap<String, ValueMatch>),
+}
+
+/// Matches a value either exactly (`method: POST`) or with a comparison
+/// (`path: { matches: "^/v2/" }`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ValueMatch {
+    Exact(#[serde(deserialize_with = "scalar::string")] String),
+    Compare(Comparison),
+}
+
+/// Comparison operators. Every operator that is set must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Comparison {
+    #[serde(default, deserialize_with = "scalar::option", skip_serializing_if = "Option::is_none")]
     pub equals: Option<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "scalar::option", skip_serializing_if = "Option::is_none")]
     pub contains: Option<String>,
-    #[serde(default)]
-    pub matches: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Pattern>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    #[serde(
+        default,
+        rename = "in",
+        deserialize_with = "scalar::option_list",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub one_of: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MockConfig {
     #[serde(default)]
     pub server: ServerConfig,
+    /// Other config files or globs, relative to the file that includes them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// An OpenAPI 3 document, relative to this file, whose operations are
+    /// served as endpoints.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openapi: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scenarios: BTreeMap<String, ScenarioConfig>,
+    /// Where requests that match no endpoint are forwarded, if anywhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
     #[serde(default)]
     pub endpoints: Vec<EndpointConfig>,
+    /// A gRPC listener serving mocked methods of `.proto` services.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<GrpcConfig>,
 }
 
-impl Default for MockConfig {
+/// The gRPC listener. The `.proto` files are loaded once at startup; the
+/// methods are read on every call, so they reload like endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    #[serde(default = "default_grpc_port")]
+    pub port: u16,
+    /// `.proto` files, relative to the config file that names them.
+    pub protos: Vec<PathBuf>,
+    /// Directories imports are looked up in, besides each proto's own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub import_paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub methods: Vec<GrpcMethodConfig>,
+}
+
+fn default_grpc_port() -> u16 {
+    50051
+}
+
+/// A mocked method, named `package.Service/Method`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcMethodConfig {
+    pub method: String,
+    #[serde(flatten)]
+    pub reply: GrpcReply,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<DelayConfig>,
+    /// Alternative replies; the first whose condition matches the request
+    /// (message as `body`, metadata as headers) is used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<GrpcCondition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcCondition {
+    #[serde(rename = "if")]
+    pub condition: ConditionCheck,
+    #[serde(flatten)]
+    pub reply: GrpcReply,
+}
+
+/// What a call gets back. Messages are JSON templates in the protobuf JSON
+/// mapping, converted to the method's output type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GrpcReply {
+    /// The response message of a unary call, sent only when the status is OK.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<BodyTemplate>,
+    /// The messages a server-streaming call sends, before its status.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stream: Vec<GrpcStreamMessage>,
+    #[serde(default)]
+    pub status: GrpcStatus,
+    /// The status message (`grpc-message`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Template>,
+    /// Response metadata, sent as headers.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Template>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcStreamMessage {
+    pub message: BodyTemplate,
+    /// Wait before sending the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<DelayConfig>,
+}
+
+/// A gRPC status, as a code (`5`) or a name (`NOT_FOUND`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GrpcStatus {
+    Code(u8),
+    Name(String),
+}
+
+const GRPC_STATUS_NAMES: [&str; 17] = [
+    "OK",
+    "CANCELLED",
+    "UNKNOWN",
+    "INVALID_ARGUMENT",
+    "DEADLINE_EXCEEDED",
+    "NOT_FOUND",
+    "ALREADY_EXISTS",
+    "PERMISSION_DENIED",
+    "RESOURCE_EXHAUSTED",
+    "FAILED_PRECONDITION",
+    "ABORTED",
+    "OUT_OF_RANGE",
+    "UNIMPLEMENTED",
+    "INTERNAL",
+    "UNAVAILABLE",
+    "DATA_LOSS",
+    "UNAUTHENTICATED",
+];
+
+impl Default for GrpcStatus {
     fn default() -> Self {
-        Self {
-            server: ServerConfig::default(),
-            endpoints: Vec::new(),
-        }
+        GrpcStatus::Code(0)
+    }
+}
+
+impl GrpcStatus {
+    /// The numeric code, or None for an unknown name or code.
+    pub fn code(&self) -> Option<u8> {
+        let code = match self {
+            GrpcStatus::Code(code) => *code,
+            GrpcStatus::Name(name) => GRPC_STATUS_NAMES
+                .iter()
+                .position(|known| known.eq_ignore_ascii_case(name))? as u8,
+        };
+        (usize::from(code) < GRPC_STATUS_NAMES.len()).then_some(code)
     }
 }
 
-#[allow(dead_code)]
 pub struct ConfigManager {
     config: Arc<RwLock<MockConfig>>,
     config_path: String,
+    sources: RwLock<Vec<PathBuf>>,
 }
 
 impl ConfigManager {
     pub async fn new(path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let config = load_config(path)?;
+        let loaded = load_config_sources(path)?;
         
         Ok(Self {
-            config: Arc::new(RwLock::new(config)),
+            config: Arc::new(RwLock::new(loaded.config)),
             config_path: path.to_string(),
+            sources: RwLock::new(loaded.files),
         })
     }
 
@@ -155,10 +878,21 @@ impl ConfigManager {
         self.config.read().clone()
     }
 
-    #[allow(dead_code)]
+    pub fn config_path(&self) -> &str {
+        &self.config_path
+    }
+
+    /// Every file the current config was loaded from, including includes.
+    pub fn sources(&self) -> Vec<PathBuf> {
+        self.sources.read().clone()
+    }
+
+    /// Re-reads the config file. The running config is only replaced once
+    /// the new one has parsed and validated.
     pub fn reload(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let new_config = load_config(&self.config_path)?;
-        *self.config.write() = new_config;
+        let loaded = load_config_sources(&self.config_path)?;
+        *self.config.write() = loaded.config;
+        *self.sources.write() = loaded.files;
         tracing::info!("Configuration reloaded successfully");
         Ok(())
     }
@@ -0,0 +1,90 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use super::EndpointConfig;
+
+/// The recorded exchanges of each file written to, so a repeated exchange
+/// skips reading the file. The lock also serialises writes across
+/// concurrent requests.
+static RECORDED: LazyLock<Mutex<HashMap<PathBuf, Recorded>>> = LazyLock::new(Default::default);
+
+/// The exchanges a recording file holds as of its last modification.
+struct Recorded {
+    modified: SystemTime,
+    keys: HashSet<String>,
+}
+
+/// The shape of a recording file: a plain config file holding endpoints
+/// only, so it can be loaded or included like any other.
+#[derive(Default, Serialize, Deserialize)]
+struct Recording {
+    #[serde(default)]
+    endpoints: Vec<EndpointConfig>,
+}
+
+/// Appends a recorded endpoint to a YAML config file, creating it if needed.
+/// An exchange already recorded for the same method, path and query is kept
+/// as it is, so the file always loads without duplicate routes.
+///
+/// This blocks on file I/O, so async callers run it on the blocking pool.
+pub fn record_endpoint(
+    file: &Path,
+    endpoint: EndpointConfig,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let mut recorded = RECORDED.lock();
+
+    let key = exchange_key(&endpoint);
+    let modified = fs::metadata(file).and_then(|m| m.modified()).ok();
+    if let (Some(modified), Some(seen)) = (modified, recorded.get(file)) {
+        if seen.modified == modified && seen.keys.contains(&key) {
+            return Ok(false);
+        }
+    }
+
+    let mut recording: Recording = if file.exists() {
+        let content = fs::read_to_string(file)?;
+        serde_yaml::from_str(&content).map_err(|e| format!("{}: {}", file.display(), e))?
+    } else {
+        Recording::default()
+    };
+    let mut keys: HashSet<String> = recording.endpoints.iter().map(exchange_key).collect();
+
+    let added = keys.insert(key);
+    if added {
+        recording.endpoints.push(endpoint);
+        write_atomically(file, &serde_yaml::to_string(&recording)?)?;
+    }
+    if let Ok(modified) = fs::metadata(file).and_then(|m| m.modified()) {
+        recorded.insert(file.to_path_buf(), Recorded { modified, keys });
+    }
+    Ok(added)
+}
+
+/// What makes two recorded exchanges the same: method, path and query.
+fn exchange_key(endpoint: &EndpointConfig) -> String {
+    format!(
+        "{} {} {}",
+        endpoint.method.to_uppercase(),
+        endpoint.path,
+        serde_json::to_string(&endpoint.query).unwrap_or_default()
+    )
+}
+
+/// Writes to a temporary file next to `file` and renames it into place, so
+/// a crash or a reload never sees a half-written recording. The temporary
+/// name does not end in a config extension, so the watcher ignores it.
+fn write_atomically(file: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = file.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut temp_name = file.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp = file.with_file_name(temp_name);
+    fs::write(&temp, content)?;
+    fs::rename(&temp, file)
+}
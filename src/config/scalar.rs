@@ -0,0 +1,31 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// Accepts any YAML/JSON scalar where the config wants a string, so
+/// `equals: 999` and `equals: "999"` mean the same thing.
+fn to_string<E: serde::de::Error>(value: Value) -> Result<String, E> {
+    match value {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        other => Err(E::custom(format!("expected a string, number or boolean, got {}", other))),
+    }
+}
+
+pub fn string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    to_string(Value::deserialize(deserializer)?)
+}
+
+pub fn option<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Option::<Value>::deserialize(deserializer)?
+        .map(to_string)
+        .transpose()
+}
+
+pub fn option_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+    Option::<Vec<Value>>::deserialize(deserializer)?
+        .map(|values| values.into_iter().map(to_string).collect())
+        .transpose()
+}
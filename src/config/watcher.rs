@@ -0,0 +1,194 @@
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+
+use super::{is_config_file, ConfigManager};
+
+/// How long the config file has to stay quiet before it is reloaded. Editors
+/// usually write, rename and touch a file several times per save.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches the config file (or directory), every file it includes and every
+/// file it references, and reloads the config whenever one of them changes. The watcher lives as long
+/// as the spawned task, which runs for the rest of the process.
+pub fn watch_config(config_manager: Arc<ConfigManager>) -> notify::Result<()> {
+    let root = PathBuf::from(config_manager.config_path());
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            let _ = tx.send(event);
+        }
+        Err(e) => tracing::warn!("Config watcher error: {}", e),
+    })?;
+
+    let mut watched = HashSet::new();
+    update_watches(&mut watcher, &mut watched, &root, &config_manager.sources());
+
+    tracing::info!("Watching {} for changes", root.display());
+
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            if !is_relevant(&event, &config_manager.sources()) {
+                continue;
+            }
+
+            // Wait for the burst of events from a single save to settle
+            loop {
+                match timeout(DEBOUNCE, rx.recv()).await {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => return,
+                    Err(_) => break,
+                }
+            }
+
+            match config_manager.reload() {
+                // Includes may have changed, so new directories may need watching
+                Ok(()) => update_watches(&mut watcher, &mut watched, &root, &config_manager.sources()),
+                Err(e) => tracing::error!("Keeping the last good configuration: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Watches the directory of every config file rather than the files
+/// themselves: editors often replace a file through a rename, which would
+/// silently end a watch placed on the file, and new files in a config
+/// directory have to be noticed too.
+fn update_watches(
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+    root: &Path,
+    sources: &[PathBuf],
+) {
+    let root_dir = if root.is_dir() {
+        root.to_path_buf()
+    } else {
+        match root.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    };
+
+    let dirs = std::iter::once(root_dir)
+        .chain(sources.iter().filter_map(|f| f.parent().map(Path::to_path_buf)))
+        .filter_map(|dir| dir.canonicalize().ok());
+
+    for dir in dirs {
+        if watched.contains(&dir) {
+            continue;
+        }
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                watched.insert(dir);
+            }
+            Err(e) => tracing::warn!("Cannot watch {}: {}", dir.display(), e),
+        }
+    }
+}
+
+/// Whether an event touches a config file, or a file the config references
+/// whatever its extension (a `bodyFile`, an SDL or `.proto` file).
+fn is_relevant(event: &Event, sources: &[PathBuf]) -> bool {
+    let changes_content = matches!(
+        event.kind,
+        EventKind::Create(_)
+            | EventKind::Modify(_)
+            | EventKind::Remove(_)
+            | EventKind::Access(AccessKind::Close(AccessMode::Write))
+    );
+
+    changes_content
+        && event
+            .paths
+            .iter()
+            .any(|p| is_config_file(p) || sources.contains(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const ENDPOINTS: &str = r#"
+endpoints:
+  - path: "/first"
+    response: { body: null }
+"#;
+
+    #[tokio::test]
+    async fn test_file_changes_are_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("endpoints.yaml");
+        fs::write(&path, ENDPOINTS).unwrap();
+
+        let manager = Arc::new(ConfigManager::new(path.to_str().unwrap()).await.unwrap());
+        watch_config(manager.clone()).unwrap();
+
+        fs::write(&path, ENDPOINTS.replace("/first", "/second")).unwrap();
+
+        for _ in 0..50 {
+            if manager.get_config().endpoints[0].path == "/second" {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("config was not reloaded");
+    }
+
+    #[tokio::test]
+    async fn test_referenced_files_are_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("schema.graphql"), "type Query { hello: String }\n").unwrap();
+        let path = dir.path().join("endpoints.yaml");
+        fs::write(
+            &path,
+            "endpoints:\n  - path: /graphql\n    method: POST\n    graphql: { schema: schema.graphql }\n    response: { body: null }\n",
+        )
+        .unwrap();
+
+        let manager = Arc::new(ConfigManager::new(path.to_str().unwrap()).await.unwrap());
+        watch_config(manager.clone()).unwrap();
+
+        fs::write(dir.path().join("schema.graphql"), "type Query { hi: String }\n").unwrap();
+
+        for _ in 0..50 {
+            let config = manager.get_config();
+            let schema = config.endpoints[0].graphql.as_ref().unwrap().parsed_schema().unwrap();
+            if schema.fields("Query").is_some_and(|fields| fields.contains_key("hi")) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("referenced file change was not reloaded");
+    }
+
+    #[tokio::test]
+    async fn test_files_added_to_a_directory_are_picked_up() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("first.yaml"), ENDPOINTS).unwrap();
+
+        let manager = Arc::new(ConfigManager::new(dir.path().to_str().unwrap()).await.unwrap());
+        watch_config(manager.clone()).unwrap();
+
+        fs::write(
+            dir.path().join("second.yaml"),
+            ENDPOINTS.replace("/first", "/second"),
+        )
+        .unwrap();
+
+        for _ in 0..50 {
+            if manager.get_config().endpoints.len() == 2 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("new file was not picked up");
+    }
+}
@@ -0,0 +1,668 @@
+use graphql_parser::query::{
+    self, Definition, Directive, Field, FragmentDefinition, OperationDefinition, Selection,
+    SelectionSet, TypeCondition, VariableDefinition,
+};
+use graphql_parser::Pos;
+use rand::seq::SliceRandom;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::{Schema, TypeDef, TypeRef};
+use crate::config::GraphQLConfig;
+use crate::request::{Generator, RequestContext};
+use crate::response::{generate_fake_value, generate_response_body, value_to_string};
+
+/// A GraphQL request as sent in a POST body or GET query string.
+#[derive(Debug, Default)]
+pub struct GraphQLRequest {
+    pub query: String,
+    pub variables: Map<String, Value>,
+    pub operation_name: Option<String>,
+}
+
+/// An entry of a response's `errors` array.
+#[derive(Debug, PartialEq)]
+pub struct GraphQLError {
+    pub message: String,
+    pub locations: Vec<Pos>,
+}
+
+impl GraphQLError {
+    pub fn new(message: impl Into<String>, position: Option<Pos>) -> Self {
+        Self {
+            message: message.into(),
+            locations: position.into_iter().collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut error = json!({ "message": self.message });
+        if !self.locations.is_empty() {
+            error["locations"] = self
+                .locations
+                .iter()
+                .map(|pos| json!({ "line": pos.line, "column": pos.column }))
+                .collect();
+        }
+        error
+    }
+}
+
+/// The body of a response that only reports errors.
+pub fn errors_response(errors: &[GraphQLError]) -> Value {
+    json!({ "errors": errors.iter().map(GraphQLError::to_json).collect::<Vec<_>>() })
+}
+
+/// Runs a request against the schema and returns the response body:
+/// `{"data": ...}` when the query is valid, `{"errors": [...]}` otherwise.
+pub fn execute(
+    schema: &Schema,
+    config: &GraphQLConfig,
+    request: &GraphQLRequest,
+    ctx: &RequestContext,
+) -> Value {
+    let document = match query::parse_query::<String>(&request.query) {
+        Ok(document) => document,
+        Err(e) => return errors_response(&[syntax_error(&e.to_string())]),
+    };
+
+    let mut operations = Vec::new();
+    let mut fragments = HashMap::new();
+    for definition in &document.definitions {
+        match definition {
+            Definition::Operation(operation) => operations.push(Operation::from(operation)),
+            Definition::Fragment(fragment) => {
+                fragments.insert(fragment.name.clone(), fragment);
+            }
+        }
+    }
+
+    let operation = match select_operation(&operations, request.operation_name.as_deref()) {
+        Ok(operation) => operation,
+        Err(error) => return errors_response(&[error]),
+    };
+    let root = match operation.kind {
+        OperationKind::Query => Some(&schema.query),
+        OperationKind::Mutation => schema.mutation.as_ref(),
+        OperationKind::Subscription => {
+            let message = match schema.subscription {
+                Some(_) => "Subscriptions are not supported.",
+                None => "Schema is not configured for subscriptions.",
+            };
+            return errors_response(&[GraphQLError::new(message, Some(operation.position))]);
+        }
+    };
+    let Some(root) = root else {
+        return errors_response(&[GraphQLError::new(
+            "Schema is not configured for mutations.",
+            Some(operation.position),
+        )]);
+    };
+
+    let mut executor = Executor {
+        schema,
+        config,
+        fragments,
+        variables: request.variables.clone(),
+    };
+    executor.apply_variable_defaults(operation.variables);
+
+    let mut errors = Vec::new();
+    executor.validate(root, &operation.selection_set.items, &mut Vec::new(), &mut errors);
+    if !errors.is_empty() {
+        return errors_response(&errors);
+    }
+
+    let selections: Vec<&Selection<String>> = operation.selection_set.items.iter().collect();
+    json!({ "data": executor.execute_selections(root, None, &selections, ctx) })
+}
+
+enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+struct Operation<'q> {
+    kind: OperationKind,
+    name: Option<&'q str>,
+    position: Pos,
+    variables: &'q [VariableDefinition<'q, String>],
+    selection_set: &'q SelectionSet<'q, String>,
+}
+
+impl<'q> From<&'q OperationDefinition<'q, String>> for Operation<'q> {
+    fn from(operation: &'q OperationDefinition<'q, String>) -> Self {
+        match operation {
+            OperationDefinition::SelectionSet(selection_set) => Operation {
+                kind: OperationKind::Query,
+                name: None,
+                position: selection_set.span.0,
+                variables: &[],
+                selection_set,
+            },
+            OperationDefinition::Query(q) => Operation {
+                kind: OperationKind::Query,
+                name: q.name.as_deref(),
+                position: q.position,
+                variables: &q.variable_definitions,
+                selection_set: &q.selection_set,
+            },
+            OperationDefinition::Mutation(m) => Operation {
+                kind: OperationKind::Mutation,
+                name: m.name.as_deref(),
+                position: m.position,
+                variables: &m.variable_definitions,
+                selection_set: &m.selection_set,
+            },
+            OperationDefinition::Subscription(s) => Operation {
+                kind: OperationKind::Subscription,
+                name: s.name.as_deref(),
+                position: s.position,
+                variables: &s.variable_definitions,
+                selection_set: &s.selection_set,
+            },
+        }
+    }
+}
+
+fn select_operation<'a, 'q>(
+    operations: &'a [Operation<'q>],
+    name: Option<&str>,
+) -> Result<&'a Operation<'q>, GraphQLError> {
+    match name {
+        Some(name) => operations
+            .iter()
+            .find(|operation| operation.name == Some(name))
+            .ok_or_else(|| GraphQLError::new(format!("Unknown operation named \"{}\".", name), None)),
+        None => match operations {
+            [] => Err(GraphQLError::new("Must provide an operation.", None)),
+            [operation] => Ok(operation),
+            _ => Err(GraphQLError::new(
+                "Must provide operation name if query contains multiple operations.",
+                None,
+            )),
+        },
+    }
+}
+
+/// Turns a parser message such as "query parse error: Parse error at 1:15\n
+/// Unexpected `}[Punctuator]`\nExpected Name\n" into a spec-style error.
+fn syntax_error(message: &str) -> GraphQLError {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    static TOKEN_KIND: OnceLock<Regex> = OnceLock::new();
+    let location = LOCATION.get_or_init(|| Regex::new(r"at (\d+):(\d+)").expect("valid regex"));
+    let token_kind = TOKEN_KIND.get_or_init(|| Regex::new(r"\[\w+\]`").expect("valid regex"));
+
+    let position = location.captures(message).map(|captures| Pos {
+        line: captures[1].parse().unwrap_or(1),
+        column: captures[2].parse().unwrap_or(1),
+    });
+    let details: Vec<String> = message
+        .lines()
+        .skip(1)
+        .filter(|line| !line.is_empty())
+        .map(|line| token_kind.replace_all(line, "`").into_owned())
+        .collect();
+    GraphQLError::new(format!("Syntax Error: {}.", details.join(". ")), position)
+}
+
+struct Executor<'q> {
+    schema: &'q Schema,
+    config: &'q GraphQLConfig,
+    fragments: HashMap<String, &'q FragmentDefinition<'q, String>>,
+    variables: Map<String, Value>,
+}
+
+impl<'q> Executor<'q> {
+    fn apply_variable_defaults(&mut self, definitions: &[VariableDefinition<'q, String>]) {
+        for definition in definitions {
+            if let Some(default) = &definition.default_value {
+                if !self.variables.contains_key(&definition.name) {
+                    let value = self.to_json(default);
+                    self.variables.insert(definition.name.clone(), value);
+                }
+            }
+        }
+    }
+
+    /// Checks the selections against the schema, collecting spec-style
+    /// errors for unknown fields, arguments, fragments and types, missing
+    /// required arguments and leaf/composite selection mismatches.
+    fn validate(
+        &self,
+        type_name: &str,
+        selections: &'q [Selection<'q, String>],
+        spreading: &mut Vec<&'q str>,
+        errors: &mut Vec<GraphQLError>,
+    ) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => self.validate_field(type_name, field, spreading, errors),
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.fragment_name.as_str();
+                    let Some(fragment) = self.fragments.get(name) else {
+                        errors.push(GraphQLError::new(
+                            format!("Unknown fragment \"{}\".", name),
+                            Some(spread.position),
+                        ));
+                        continue;
+                    };
+                    if spreading.contains(&name) {
+                        errors.push(GraphQLError::new(
+                            format!("Cannot spread fragment \"{}\" within itself.", name),
+                            Some(spread.position),
+                        ));
+                        continue;
+                    }
+                    let TypeCondition::On(condition) = &fragment.type_condition;
+                    if self.check_type_condition(condition, fragment.position, errors) {
+                        spreading.push(name);
+                        self.validate(condition, &fragment.selection_set.items, spreading, errors);
+                        spreading.pop();
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let condition = match &fragment.type_condition {
+                        Some(TypeCondition::On(condition)) => condition.as_str(),
+                        None => type_name,
+                    };
+                    if self.check_type_condition(condition, fragment.position, errors) {
+                        self.validate(condition, &fragment.selection_set.items, spreading, errors);
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_field(
+        &self,
+        type_name: &str,
+        field: &'q Field<'q, String>,
+        spreading: &mut Vec<&'q str>,
+        errors: &mut Vec<GraphQLError>,
+    ) {
+        let position = Some(field.position);
+        if field.name == "__typename" {
+            if !field.selection_set.items.is_empty() {
+                errors.push(GraphQLError::new(
+                    "Field \"__typename\" must not have a selection since type \"String!\" has no subfields.",
+                    position,
+                ));
+            }
+            return;
+        }
+        let Some(definition) = self.schema.fields(type_name).and_then(|f| f.get(&field.name)) else {
+            errors.push(GraphQLError::new(
+                format!("Cannot query field \"{}\" on type \"{}\".", field.name, type_name),
+                position,
+            ));
+            return;
+        };
+
+        for (name, _) in &field.arguments {
+            if !definition.arguments.iter().any(|arg| &arg.name == name) {
+                errors.push(GraphQLError::new(
+                    format!("Unknown argument \"{}\" on field \"{}.{}\".", name, type_name, field.name),
+                    position,
+                ));
+            }
+        }
+        for argument in &definition.arguments {
+            let required = matches!(argument.value_type, TypeRef::NonNull(_)) && !argument.has_default;
+            if required && !field.arguments.iter().any(|(name, _)| name == &argument.name) {
+                errors.push(GraphQLError::new(
+                    format!(
+                        "Field \"{}\" argument \"{}\" of type \"{}\" is required, but it was not provided.",
+                        field.name, argument.name, argument.value_type
+                    ),
+                    position,
+                ));
+            }
+        }
+
+        let field_type = definition.field_type.named();
+        let has_selection = !field.selection_set.items.is_empty();
+        if self.schema.is_composite(field_type) {
+            if has_selection {
+                self.validate(field_type, &field.selection_set.items, spreading, errors);
+            } else {
+                errors.push(GraphQLError::new(
+                    format!(
+                        "Field \"{}\" of type \"{}\" must have a selection of subfields. Did you mean \"{} {{ ... }}\"?",
+                        field.name, definition.field_type, field.name
+                    ),
+                    position,
+                ));
+            }
+        } else if has_selection {
+            errors.push(GraphQLError::new(
+                format!(
+                    "Field \"{}\" must not have a selection since type \"{}\" has no subfields.",
+                    field.name, definition.field_type
+                ),
+                position,
+            ));
+        }
+    }
+
+    fn check_type_condition(&self, condition: &str, position: Pos, errors: &mut Vec<GraphQLError>) -> bool {
+        if self.schema.is_composite(condition) {
+            return true;
+        }
+        let message = if self.schema.types.contains_key(condition) {
+            format!("Fragment cannot condition on non composite type \"{}\".", condition)
+        } else {
+            format!("Unknown type \"{}\".", condition)
+        };
+        errors.push(GraphQLError::new(message, Some(position)));
+        false
+    }
+
+    /// Resolves the selected fields of an object type. `source` is the value
+    /// the parent field resolved to, if any.
+    fn execute_selections(
+        &self,
+        type_name: &str,
+        source: Option<&Map<String, Value>>,
+        selections: &[&'q Selection<'q, String>],
+        ctx: &RequestContext,
+    ) -> Value {
+        let mut grouped = Vec::new();
+        self.collect_fields(type_name, selections, &mut grouped, &mut Vec::new());
+
+        let mut data = Map::new();
+        for (key, fields) in grouped {
+            let field = fields[0];
+            if field.name == "__typename" {
+                data.insert(key, Value::String(type_name.to_string()));
+                continue;
+            }
+            let Some(definition) = self.schema.fields(type_name).and_then(|f| f.get(&field.name)) else {
+                continue;
+            };
+
+            // Arguments are exposed as params, on top of the parent's
+            let mut field_ctx = ctx.clone();
+            for (name, value) in &field.arguments {
+                field_ctx
+                    .params
+                    .insert(name.clone(), value_to_string(&self.to_json(value)));
+            }
+
+            let value = match self.resolver(type_name, &field.name) {
+                Some(resolver) => Some(generate_response_body(resolver, &field_ctx)),
+                None => source.and_then(|source| source.get(&field.name)).cloned(),
+            };
+            let subselections: Vec<&Selection<String>> = fields
+                .iter()
+                .flat_map(|f| f.selection_set.items.iter())
+                .collect();
+            let completed = self.complete_value(
+                &definition.field_type,
+                value,
+                &field.name,
+                &subselections,
+                &field_ctx,
+            );
+            data.insert(key, completed);
+        }
+        Value::Object(data)
+    }
+
+    /// Groups the fields that apply to `type_name` by response key, following
+    /// fragments and honouring `@skip` and `@include`.
+    fn collect_fields(
+        &self,
+        type_name: &str,
+        selections: &[&'q Selection<'q, String>],
+        grouped: &mut Vec<(String, Vec<&'q Field<'q, String>>)>,
+        spreading: &mut Vec<&'q str>,
+    ) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    if !self.included(&field.directives) {
+                        continue;
+                    }
+                    let key = field.alias.as_ref().unwrap_or(&field.name);
+                    match grouped.iter_mut().find(|(existing, _)| existing == key) {
+                        Some((_, fields)) => fields.push(field),
+                        None => grouped.push((key.clone(), vec![field])),
+                    }
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.fragment_name.as_str();
+                    let Some(fragment) = self.fragments.get(name) else {
+                        continue;
+                    };
+                    let TypeCondition::On(condition) = &fragment.type_condition;
+                    if !self.included(&spread.directives)
+                        || spreading.contains(&name)
+                        || !self.applies(type_name, condition)
+                    {
+                        continue;
+                    }
+                    spreading.push(name);
+                    let items: Vec<_> = fragment.selection_set.items.iter().collect();
+                    self.collect_fields(type_name, &items, grouped, spreading);
+                    spreading.pop();
+                }
+                Selection::InlineFragment(fragment) => {
+                    let applies = match &fragment.type_condition {
+                        Some(TypeCondition::On(condition)) => self.applies(type_name, condition),
+                        None => true,
+                    };
+                    if applies && self.included(&fragment.directives) {
+                        let items: Vec<_> = fragment.selection_set.items.iter().collect();
+                        self.collect_fields(type_name, &items, grouped, spreading);
+                    }
+                }
+            }
+        }
+    }
+
+    fn complete_value(
+        &self,
+        field_type: &TypeRef,
+        value: Option<Value>,
+        field_name: &str,
+        selections: &[&'q Selection<'q, String>],
+        ctx: &RequestContext,
+    ) -> Value {
+        match field_type {
+            TypeRef::NonNull(inner) => self.complete_value(inner, value, field_name, selections, ctx),
+            TypeRef::List(inner) => match value {
+                Some(Value::Null) => Value::Null,
+                Some(Value::Array(items)) => items
+                    .into_iter()
+                    .map(|item| self.complete_value(inner, Some(item), field_name, selections, ctx))
+                    .collect(),
+                Some(single) => {
+                    Value::Array(vec![self.complete_value(inner, Some(single), field_name, selections, ctx)])
+                }
+                None => (0..self.config.list_length)
+                    .map(|_| self.complete_value(inner, None, field_name, selections, ctx))
+                    .collect(),
+            },
+            TypeRef::Named(type_name) => {
+                if value == Some(Value::Null) {
+                    return Value::Null;
+                }
+                match self.schema.types.get(type_name) {
+                    Some(TypeDef::Scalar) => {
+                        value.unwrap_or_else(|| {
+                            ctx.rng.with(|rng| fake_scalar(type_name, field_name, rng))
+                        })
+                    }
+                    Some(TypeDef::Enum { values }) => value.unwrap_or_else(|| {
+                        ctx.rng
+                            .with(|rng| values.choose(rng))
+                            .map(|v| Value::String(v.clone()))
+                            .unwrap_or(Value::Null)
+                    }),
+                    Some(TypeDef::Object { .. } | TypeDef::Interface { .. } | TypeDef::Union { .. }) => {
+                        let source = value.as_ref().and_then(Value::as_object);
+                        let concrete = self.concrete_type(type_name, source);
+                        self.execute_selections(&concrete, source, selections, ctx)
+                    }
+                    Some(TypeDef::InputObject) | None => Value::Null,
+                }
+            }
+        }
+    }
+
+    /// The object type an abstract value is: the `__typename` its source
+    /// names, or the first possible type.
+    fn concrete_type(&self, type_name: &str, source: Option<&Map<String, Value>>) -> String {
+        let possible = self.schema.possible_types(type_name);
+        source
+            .and_then(|source| source.get("__typename"))
+            .and_then(Value::as_str)
+            .filter(|named| possible.contains(named))
+            .or_else(|| possible.first().copied())
+            .unwrap_or(type_name)
+            .to_string()
+    }
+
+    /// Whether fields under a type condition apply to an object type.
+    fn applies(&self, object_type: &str, condition: &str) -> bool {
+        condition == object_type || self.schema.possible_types(condition).contains(&object_type)
+    }
+
+    fn included(&self, directives: &[Directive<'q, String>]) -> bool {
+        directives.iter().all(|directive| {
+            let condition = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "if")
+                .map(|(_, value)| self.to_json(value))
+                .and_then(|value| value.as_bool());
+            match directive.name.as_str() {
+                "skip" => condition != Some(true),
+                "include" => condition != Some(false),
+                _ => true,
+            }
+        })
+    }
+
+    fn resolver(&self, type_name: &str, field_name: &str) -> Option<&crate::response::BodyTemplate> {
+        self.config.resolvers.get(type_name)?.get(field_name)
+    }
+
+    fn to_json(&self, value: &query::Value<'q, String>) -> Value {
+        match value {
+            query::Value::Variable(name) => self.variables.get(name).cloned().unwrap_or(Value::Null),
+            query::Value::Int(number) => number.as_i64().map(Value::from).unwrap_or(Value::Null),
+            query::Value::Float(number) => json!(number),
+            query::Value::String(text) => Value::String(text.clone()),
+            query::Value::Boolean(flag) => Value::Bool(*flag),
+            query::Value::Null => Value::Null,
+            query::Value::Enum(name) => Value::String(name.clone()),
+            query::Value::List(items) => items.iter().map(|item| self.to_json(item)).collect(),
+            query::Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), self.to_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// A fake value for a scalar. `String` fields are faked by field name when it
+/// is a known fake type (`email`, `city`, ...); custom scalars by type name
+/// (`DateTime`, `URL`, ...).
+fn fake_scalar(type_name: &str, field_name: &str, rng: &mut Generator) -> Value {
+    let no_options = Map::new();
+    match type_name {
+        "ID" => generate_fake_value("uuid", &no_options, rng),
+        "Int" => generate_fake_value("number", &no_options, rng),
+        "Float" => generate_fake_value("float", &no_options, rng),
+        "Boolean" => generate_fake_value("boolean", &no_options, rng),
+        "String" => match generate_fake_value(&field_name.to_lowercase(), &no_options, rng) {
+            Value::String(text) => Value::String(text),
+            other => Value::String(value_to_string(&other)),
+        },
+        custom => generate_fake_value(&custom.to_lowercase(), &no_options, rng),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDL: &str = r#"
+        interface Node { id: ID! }
+        type User implements Node { id: ID! name: String! email: String posts: [Post!]! }
+        type Post implements Node { id: ID! title: String! }
+        type Query { user(id: ID!): User users: [User!]! node(id: ID!): Node }
+    "#;
+
+    fn run(config_yaml: &str, query: &str, variables: Value) -> Value {
+        let mut config: GraphQLConfig = serde_yaml::from_str(config_yaml).unwrap();
+        config.sdl = Some(SDL.to_string());
+        let schema = Schema::parse(SDL).unwrap();
+        let request = GraphQLRequest {
+            query: query.to_string(),
+            variables: variables.as_object().cloned().unwrap_or_default(),
+            operation_name: None,
+        };
+        execute(&schema, &config, &request, &RequestContext::default())
+    }
+
+    #[test]
+    fn test_data_follows_selection_set() {
+        let result = run(
+            r#"
+list_length: 2
+resolvers:
+  Query:
+    user: { id: "{{params.id}}", name: "Ana", posts: [{ title: "First" }] }
+  User:
+    email: "{{params.id}}@example.com"
+"#,
+            r#"
+            query Q($id: ID!) {
+                user(id: $id) { id who: name email posts { title ...Ids } }
+                users { __typename }
+            }
+            fragment Ids on Node { id }
+            "#,
+            json!({ "id": "7" }),
+        );
+
+        let user = &result["data"]["user"];
+        assert_eq!(user["id"], "7");
+        assert_eq!(user["who"], "Ana");
+        assert_eq!(user["email"], "7@example.com");
+        assert_eq!(user.as_object().unwrap().len(), 4);
+        assert_eq!(user["posts"][0]["title"], "First");
+        assert!(user["posts"][0]["id"].is_string());
+        assert_eq!(result["data"]["users"], json!([{ "__typename": "User" }, { "__typename": "User" }]));
+    }
+
+    #[test]
+    fn test_invalid_queries_get_errors() {
+        let result = run("{}", "{ user { nope } }", json!({}));
+        assert_eq!(
+            result,
+            json!({ "errors": [
+                {
+                    "message": "Field \"user\" argument \"id\" of type \"ID!\" is required, but it was not provided.",
+                    "locations": [{ "line": 1, "column": 3 }]
+                },
+                {
+                    "message": "Cannot query field \"nope\" on type \"User\".",
+                    "locations": [{ "line": 1, "column": 10 }]
+                }
+            ]})
+        );
+
+        let result = run("{}", "{ users { id }", json!({}));
+        assert_eq!(result["errors"][0]["message"], "Syntax Error: Unexpected end of input. Expected }.");
+        assert!(result.get("data").is_none());
+    }
+}
@@ -0,0 +1,5 @@
+mod execute;
+mod schema;
+
+pub use execute::*;
+pub use schema::*;
@@ -0,0 +1,274 @@
+use graphql_parser::schema::{self, Definition, TypeDefinition, TypeExtension};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The parts of an SDL schema a mock needs: the root operation types and
+/// every named type with its fields.
+#[derive(Debug, Default)]
+pub struct Schema {
+    pub query: String,
+    pub mutation: Option<String>,
+    pub subscription: Option<String>,
+    pub types: HashMap<String, TypeDef>,
+}
+
+#[derive(Debug)]
+pub enum TypeDef {
+    Scalar,
+    Object {
+        fields: HashMap<String, FieldDef>,
+        interfaces: Vec<String>,
+    },
+    Interface {
+        fields: HashMap<String, FieldDef>,
+    },
+    Union {
+        members: Vec<String>,
+    },
+    Enum {
+        values: Vec<String>,
+    },
+    InputObject,
+}
+
+#[derive(Debug)]
+pub struct FieldDef {
+    pub field_type: TypeRef,
+    pub arguments: Vec<ArgumentDef>,
+}
+
+#[derive(Debug)]
+pub struct ArgumentDef {
+    pub name: String,
+    pub value_type: TypeRef,
+    pub has_default: bool,
+}
+
+/// A field or argument type such as `[User!]!`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeRef {
+    Named(String),
+    List(Box<TypeRef>),
+    NonNull(Box<TypeRef>),
+}
+
+const BUILT_IN_SCALARS: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+
+impl Schema {
+    /// Parses an SDL document. Type extensions are merged into the types
+    /// they extend; without a `schema` block the root types are `Query`,
+    /// `Mutation` and `Subscription`.
+    pub fn parse(sdl: &str) -> Result<Self, String> {
+        let document = schema::parse_schema::<String>(sdl).map_err(|e| e.to_string())?;
+        let mut schema = Schema::default();
+        for name in BUILT_IN_SCALARS {
+            schema.types.insert(name.to_string(), TypeDef::Scalar);
+        }
+
+        let mut roots = None;
+        let mut extensions = Vec::new();
+        for definition in document.definitions {
+            match definition {
+                Definition::SchemaDefinition(definition) => roots = Some(definition),
+                Definition::TypeDefinition(definition) => {
+                    let (name, type_def) = type_def(definition);
+                    schema.types.insert(name, type_def);
+                }
+                Definition::TypeExtension(extension) => extensions.push(extension),
+                Definition::DirectiveDefinition(_) => {}
+            }
+        }
+        for extension in extensions {
+            schema.extend(extension)?;
+        }
+
+        match roots {
+            Some(roots) => {
+                schema.query = roots
+                    .query
+                    .ok_or_else(|| "the schema block has no query type".to_string())?;
+                schema.mutation = roots.mutation;
+                schema.subscription = roots.subscription;
+            }
+            None => {
+                schema.query = "Query".to_string();
+                schema.mutation = Some("Mutation".to_string());
+                schema.subscription = Some("Subscription".to_string());
+            }
+        }
+        if !matches!(schema.types.get(&schema.query), Some(TypeDef::Object { .. })) {
+            return Err(format!("query type {} is not defined", schema.query));
+        }
+        for root in [&mut schema.mutation, &mut schema.subscription] {
+            if root.as_ref().is_some_and(|name| !schema.types.contains_key(name)) {
+                *root = None;
+            }
+        }
+
+        schema.check_references()?;
+        Ok(schema)
+    }
+
+    /// The fields of an object or interface type.
+    pub fn fields(&self, type_name: &str) -> Option<&HashMap<String, FieldDef>> {
+        match self.types.get(type_name)? {
+            TypeDef::Object { fields, .. } | TypeDef::Interface { fields } => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Whether values of `type_name` have fields to select.
+    pub fn is_composite(&self, type_name: &str) -> bool {
+        matches!(
+            self.types.get(type_name),
+            Some(TypeDef::Object { .. } | TypeDef::Interface { .. } | TypeDef::Union { .. })
+        )
+    }
+
+    /// The object types a value of `type_name` can be: itself for an
+    /// object, the implementors of an interface, the members of a union.
+    pub fn possible_types(&self, type_name: &str) -> Vec<&str> {
+        match self.types.get_key_value(type_name) {
+            Some((name, TypeDef::Object { .. })) => vec![name.as_str()],
+            Some((_, TypeDef::Interface { .. })) => {
+                let mut implementors: Vec<&str> = self
+                    .types
+                    .iter()
+                    .filter(|(_, def)| {
+                        matches!(def, TypeDef::Object { interfaces, .. }
+                            if interfaces.iter().any(|i| i == type_name))
+                    })
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                implementors.sort_unstable();
+                implementors
+            }
+            Some((_, TypeDef::Union { members })) => members.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn extend(&mut self, extension: TypeExtension<'_, String>) -> Result<(), String> {
+        let (name, added_fields, added_interfaces, added_members, added_values) = match extension {
+            TypeExtension::Object(ext) => (ext.name, ext.fields, ext.implements_interfaces, vec![], vec![]),
+            TypeExtension::Interface(ext) => (ext.name, ext.fields, vec![], vec![], vec![]),
+            TypeExtension::Union(ext) => (ext.name, vec![], vec![], ext.types, vec![]),
+            TypeExtension::Enum(ext) => (ext.name, vec![], vec![], vec![], ext.values),
+            TypeExtension::Scalar(_) | TypeExtension::InputObject(_) => return Ok(()),
+        };
+        let type_def = self
+            .types
+            .get_mut(&name)
+            .ok_or_else(|| format!("cannot extend undefined type {}", name))?;
+        match type_def {
+            TypeDef::Object { fields, interfaces } => {
+                fields.extend(added_fields.into_iter().map(field_def));
+                interfaces.extend(added_interfaces);
+            }
+            TypeDef::Interface { fields } => fields.extend(added_fields.into_iter().map(field_def)),
+            TypeDef::Union { members } => members.extend(added_members),
+            TypeDef::Enum { values } => values.extend(added_values.into_iter().map(|v| v.name)),
+            TypeDef::Scalar | TypeDef::InputObject => {}
+        }
+        Ok(())
+    }
+
+    /// Every type a field or argument names must be defined.
+    fn check_references(&self) -> Result<(), String> {
+        for (type_name, type_def) in &self.types {
+            let referenced: Vec<&TypeRef> = match type_def {
+                TypeDef::Object { fields, .. } | TypeDef::Interface { fields } => fields
+                    .values()
+                    .flat_map(|field| {
+                        std::iter::once(&field.field_type)
+                            .chain(field.arguments.iter().map(|arg| &arg.value_type))
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for type_ref in referenced {
+                let named = type_ref.named();
+                if !self.types.contains_key(named) {
+                    return Err(format!("type {} refers to undefined type {}", type_name, named));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TypeRef {
+    /// The named type under any list and non-null wrappers.
+    pub fn named(&self) -> &str {
+        match self {
+            TypeRef::Named(name) => name,
+            TypeRef::List(inner) | TypeRef::NonNull(inner) => inner.named(),
+        }
+    }
+}
+
+impl From<&schema::Type<'_, String>> for TypeRef {
+    fn from(value: &schema::Type<'_, String>) -> Self {
+        match value {
+            schema::Type::NamedType(name) => TypeRef::Named(name.clone()),
+            schema::Type::ListType(inner) => TypeRef::List(Box::new(inner.as_ref().into())),
+            schema::Type::NonNullType(inner) => TypeRef::NonNull(Box::new(inner.as_ref().into())),
+        }
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeRef::Named(name) => write!(f, "{}", name),
+            TypeRef::List(inner) => write!(f, "[{}]", inner),
+            TypeRef::NonNull(inner) => write!(f, "{}!", inner),
+        }
+    }
+}
+
+fn type_def(definition: TypeDefinition<'_, String>) -> (String, TypeDef) {
+    match definition {
+        TypeDefinition::Scalar(scalar) => (scalar.name, TypeDef::Scalar),
+        TypeDefinition::Object(object) => (
+            object.name,
+            TypeDef::Object {
+                fields: object.fields.into_iter().map(field_def).collect(),
+                interfaces: object.implements_interfaces,
+            },
+        ),
+        TypeDefinition::Interface(interface) => (
+            interface.name,
+            TypeDef::Interface {
+                fields: interface.fields.into_iter().map(field_def).collect(),
+            },
+        ),
+        TypeDefinition::Union(union) => (union.name, TypeDef::Union { members: union.types }),
+        TypeDefinition::Enum(enumeration) => (
+            enumeration.name,
+            TypeDef::Enum {
+                values: enumeration.values.into_iter().map(|v| v.name).collect(),
+            },
+        ),
+        TypeDefinition::InputObject(input) => (input.name, TypeDef::InputObject),
+    }
+}
+
+fn field_def(field: schema::Field<'_, String>) -> (String, FieldDef) {
+    let arguments = field
+        .arguments
+        .iter()
+        .map(|arg| ArgumentDef {
+            name: arg.name.clone(),
+            value_type: (&arg.value_type).into(),
+            has_default: arg.default_value.is_some(),
+        })
+        .collect();
+    (
+        field.name,
+        FieldDef {
+            field_type: (&field.field_type).into(),
+            arguments,
+        },
+    )
+}
@@ -0,0 +1,29 @@
+use prost_reflect::DescriptorPool;
+use protobuf::Message;
+
+use crate::config::GrpcConfig;
+
+/// Parses the config's `.proto` files, and whatever they import, into a
+/// descriptor pool. No `protoc` or code generation is involved; the
+/// well-known `google/protobuf/*` types are built in.
+pub fn load_descriptors(config: &GrpcConfig) -> Result<DescriptorPool, String> {
+    let mut parser = protobuf_parse::Parser::new();
+    parser.pure();
+    for proto in &config.protos {
+        parser.input(proto);
+        if let Some(dir) = proto.parent() {
+            parser.include(dir);
+        }
+    }
+    for dir in &config.import_paths {
+        parser.include(dir);
+    }
+    let parsed = parser
+        .parse_and_typecheck()
+        .map_err(|e| format!("{:#}", e))?;
+
+    let mut set = protobuf::descriptor::FileDescriptorSet::new();
+    set.file = parsed.file_descriptors;
+    let bytes = set.write_to_bytes().map_err(|e| e.to_string())?;
+    DescriptorPool::decode(bytes.as_slice()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,5 @@
+mod descriptors;
+mod server;
+
+pub use descriptors::*;
+pub use server::*;
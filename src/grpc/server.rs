@@ -0,0 +1,423 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Uri},
+    response::Response,
+    Router,
+};
+use futures::stream::{self, Stream};
+use http_body::Frame;
+use http_body_util::StreamBody;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::config::{DelayConfig, GrpcReply};
+use crate::middleware::apply_delay;
+use crate::request::RequestContext;
+use crate::response::{generate_response_body, BodyTemplate};
+use crate::router::{check_condition, request_rng};
+use crate::state::AppState;
+
+const OK: u8 = 0;
+const INVALID_ARGUMENT: u8 = 3;
+const UNIMPLEMENTED: u8 = 12;
+const INTERNAL: u8 = 13;
+
+/// What gRPC calls are served from: the shared app state (for the live
+/// config and the request journal) and the descriptors loaded at startup.
+#[derive(Clone)]
+pub struct GrpcState {
+    pub app: AppState,
+    pub descriptors: Arc<DescriptorPool>,
+}
+
+/// Serves gRPC calls over cleartext HTTP/2 until the listener fails.
+pub async fn serve_grpc(addr: SocketAddr, state: GrpcState) -> std::io::Result<()> {
+    let app = Router::new().fallback(handle_grpc_call).with_state(state);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("gRPC mocks listening on {}", addr);
+    axum::serve(listener, app).await
+}
+
+/// Answers one call to `/package.Service/Method`. Unary and server-streaming
+/// methods are supported; the request message is decoded to JSON so
+/// conditions and templates can use it as `body`.
+pub async fn handle_grpc_call(
+    State(state): State<GrpcState>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let name = uri.path().trim_start_matches('/').to_string();
+    tracing::info!("gRPC {}", name);
+
+    let Some(method) = name.split_once('/').and_then(|(service, method)| {
+        state
+            .descriptors
+            .get_service_by_name(service)?
+            .methods()
+            .find(|m| m.name() == method)
+    }) else {
+        return status_response(UNIMPLEMENTED, &format!("unknown method {}", name));
+    };
+    if method.is_client_streaming() {
+        return status_response(UNIMPLEMENTED, "client-streaming methods are not supported");
+    }
+
+    let request = match decode_request(method.input(), &body) {
+        Ok(request) => request,
+        Err(e) => return status_response(INVALID_ARGUMENT, &e),
+    };
+    let mut ctx = RequestContext::new(&Method::POST, &uri, headers, Some(request));
+    ctx.raw_body = body;
+
+    let config = state.app.config.get_config();
+    let Some(grpc) = &config.grpc else {
+        return status_response(UNIMPLEMENTED, "gRPC mocks are not configured");
+    };
+    let mocked = grpc
+        .methods
+        .iter()
+        .find(|m| m.method.trim_start_matches('/') == name);
+    state.app.journal.record(
+        &ctx,
+        mocked.map(|_| format!("grpc {}", name)),
+        config.server.journal_limit,
+    );
+    let Some(mocked) = mocked else {
+        return status_response(UNIMPLEMENTED, &format!("no mock configured for {}", name));
+    };
+
+    let route = format!("grpc {}", name);
+    ctx.rng = request_rng(&state.app.seeds, config.server.seed, None, &route, &ctx);
+
+    if let Some(delay) = &mocked.delay {
+        apply_delay(delay, &ctx.rng).await;
+    }
+    let reply = mocked
+        .conditions
+        .iter()
+        .find(|c| check_condition(&c.condition, &ctx))
+        .map(|c| &c.reply)
+        .unwrap_or(&mocked.reply);
+
+    let status = reply.status.code().unwrap_or(INTERNAL);
+    let messages = reply_messages(reply, status, method.is_server_streaming());
+    let status_message = reply
+        .message
+        .as_ref()
+        .map(|m| m.render(&ctx))
+        .unwrap_or_default();
+
+    let mut response = grpc_response(call_frames(
+        method.output(),
+        messages,
+        status,
+        status_message,
+        ctx.clone(),
+    ));
+    for (key, value) in &reply.metadata {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value.render(&ctx)),
+        ) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+/// The messages a reply sends. A unary call sends its `response` (an empty
+/// message if there is none) only when the status is OK; a streaming call
+/// sends its `stream`, or `response` as a single message.
+fn reply_messages(
+    reply: &GrpcReply,
+    status: u8,
+    server_streaming: bool,
+) -> Vec<(BodyTemplate, Option<DelayConfig>)> {
+    if server_streaming && !reply.stream.is_empty() {
+        return reply
+            .stream
+            .iter()
+            .map(|m| (m.message.clone(), m.delay.clone()))
+            .collect();
+    }
+    if status != OK && !server_streaming {
+        return Vec::new();
+    }
+    match &reply.response {
+        Some(response) => vec![(response.clone(), None)],
+        None if !server_streaming => {
+            vec![(
+                BodyTemplate::new(json!({})).expect("an empty object is a valid body"),
+                None,
+            )]
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Reads the single length-prefixed message of a request body.
+fn decode_request(input: MessageDescriptor, body: &[u8]) -> Result<Value, String> {
+    let payload = match body {
+        [] => &[][..],
+        [0, a, b, c, d, rest @ ..] => {
+            let length = u32::from_be_bytes([*a, *b, *c, *d]) as usize;
+            rest.get(..length).ok_or("truncated request message")?
+        }
+        [1, ..] => return Err("compressed requests are not supported".to_string()),
+        _ => return Err("malformed request frame".to_string()),
+    };
+    let message = DynamicMessage::decode(input, payload).map_err(|e| e.to_string())?;
+    message
+        .serialize_with_options(
+            serde_json::value::Serializer,
+            &SerializeOptions::new().skip_default_fields(false),
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Renders a message template and encodes it as a length-prefixed frame.
+fn encode_message(
+    output: &MessageDescriptor,
+    template: &BodyTemplate,
+    ctx: &RequestContext,
+) -> Result<Bytes, String> {
+    let json = generate_response_body(template, ctx);
+    let message = DynamicMessage::deserialize(output.clone(), json)
+        .map_err(|e| format!("response does not fit {}: {}", output.full_name(), e))?;
+    let payload = message.encode_to_vec();
+
+    let mut frame = Vec::with_capacity(payload.len() + 5);
+    frame.push(0);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(Bytes::from(frame))
+}
+
+/// Sends the messages, each after its delay, then the status trailers. A
+/// message that cannot be encoded ends the call with INTERNAL.
+fn call_frames(
+    output: MessageDescriptor,
+    messages: Vec<(BodyTemplate, Option<DelayConfig>)>,
+    status: u8,
+    status_message: String,
+    ctx: RequestContext,
+) -> impl Stream<Item = Result<Frame<Bytes>, Infallible>> + Send {
+    let call = Arc::new((output, messages, status, status_message, ctx));
+    stream::unfold(Some(0), move |next| {
+        let call = call.clone();
+        async move {
+            let index = next?;
+            let (output, messages, status, status_message, ctx) = call.as_ref();
+            let Some((template, delay)) = messages.get(index) else {
+                return Some((
+                    Ok(Frame::trailers(status_trailers(*status, status_message))),
+                    None,
+                ));
+            };
+            if let Some(delay) = delay {
+                apply_delay(delay, &ctx.rng).await;
+            }
+            match encode_message(output, template, ctx) {
+                Ok(frame) => Some((Ok(Frame::data(frame)), Some(index + 1))),
+                Err(e) => {
+                    tracing::error!("Failed to encode gRPC response: {}", e);
+                    Some((Ok(Frame::trailers(status_trailers(INTERNAL, &e))), None))
+                }
+            }
+        }
+    })
+}
+
+fn grpc_response(
+    frames: impl Stream<Item = Result<Frame<Bytes>, Infallible>> + Send + 'static,
+) -> Response {
+    let mut response = Response::new(Body::new(StreamBody::new(frames)));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/grpc"),
+    );
+    response
+}
+
+fn status_response(status: u8, message: &str) -> Response {
+    grpc_response(stream::iter([Ok(Frame::trailers(status_trailers(
+        status, message,
+    )))]))
+}
+
+fn status_trailers(status: u8, message: &str) -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from(u16::from(status)));
+    if !message.is_empty() {
+        if let Ok(value) = HeaderValue::from_str(&percent_encode(message)) {
+            trailers.insert("grpc-message", value);
+        }
+    }
+    trailers
+}
+
+/// `grpc-message` is percent-encoded outside printable ASCII.
+fn percent_encode(message: &str) -> String {
+    message
+        .bytes()
+        .map(|byte| match byte {
+            b' '..=b'~' if byte != b'%' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+    use http_body_util::BodyExt;
+
+    const PROTO: &str = r#"
+syntax = "proto3";
+package shop;
+
+import "google/protobuf/timestamp.proto";
+
+service Orders {
+  rpc GetOrder (OrderRequest) returns (Order);
+  rpc WatchOrder (OrderRequest) returns (stream Order);
+}
+
+message OrderRequest { string id = 1; }
+
+message Order {
+  string id = 1;
+  int32 quantity = 2;
+  google.protobuf.Timestamp placed_at = 3;
+}
+"#;
+
+    const CONFIG: &str = r#"
+endpoints: []
+grpc:
+  protos: [shop.proto]
+  methods:
+    - method: shop.Orders/GetOrder
+      response:
+        id: "{{body.id}}"
+        quantity: 3
+        placedAt: "2024-01-02T03:04:05Z"
+      metadata:
+        x-order: "{{body.id}}"
+      conditions:
+        - if: { body_field: "id", equals: "missing" }
+          status: NOT_FOUND
+          message: "order {{body.id}} not found"
+    - method: shop.Orders/WatchOrder
+      stream:
+        - message: { id: "{{body.id}}", quantity: 1 }
+        - message: { id: "{{body.id}}", quantity: 2 }
+"#;
+
+    async fn state() -> (tempfile::TempDir, GrpcState) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("shop.proto"), PROTO).unwrap();
+        let path = dir.path().join("endpoints.yaml");
+        std::fs::write(&path, CONFIG).unwrap();
+        let config = ConfigManager::new(path.to_str().unwrap()).await.unwrap();
+        let descriptors =
+            crate::grpc::load_descriptors(config.get_config().grpc.as_ref().unwrap()).unwrap();
+        let state = GrpcState {
+            app: AppState::new(Arc::new(config)),
+            descriptors: Arc::new(descriptors),
+        };
+        (dir, state)
+    }
+
+    /// Calls a method with an `OrderRequest` and returns the response
+    /// headers, the decoded messages and the trailers.
+    async fn call(state: &GrpcState, method: &str, id: &str) -> (HeaderMap, Vec<Value>, HeaderMap) {
+        let input = state
+            .descriptors
+            .get_message_by_name("shop.OrderRequest")
+            .unwrap();
+        let request = DynamicMessage::deserialize(input, json!({ "id": id })).unwrap();
+        let mut body = vec![0];
+        let payload = request.encode_to_vec();
+        body.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        body.extend_from_slice(&payload);
+
+        let uri: Uri = format!("/shop.Orders/{}", method).parse().unwrap();
+        let response = handle_grpc_call(
+            State(state.clone()),
+            uri,
+            HeaderMap::new(),
+            Bytes::from(body),
+        )
+        .await;
+        let (parts, body) = response.into_parts();
+        let collected = body.collect().await.unwrap();
+        let trailers = collected.trailers().cloned().unwrap_or_default();
+
+        let output = state.descriptors.get_message_by_name("shop.Order").unwrap();
+        let mut data = collected.to_bytes();
+        let mut messages = Vec::new();
+        while !data.is_empty() {
+            let length = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+            let message = DynamicMessage::decode(output.clone(), &data[5..5 + length]).unwrap();
+            messages.push(serde_json::to_value(&message).unwrap());
+            data = data.slice(5 + length..);
+        }
+        (parts.headers, messages, trailers)
+    }
+
+    #[tokio::test]
+    async fn test_unary_call_renders_the_response_message() {
+        let (_dir, state) = state().await;
+        let (headers, messages, trailers) = call(&state, "GetOrder", "A-1").await;
+        assert_eq!(headers["content-type"], "application/grpc");
+        assert_eq!(headers["x-order"], "A-1");
+        assert_eq!(
+            messages,
+            vec![json!({ "id": "A-1", "quantity": 3, "placedAt": "2024-01-02T03:04:05Z" })]
+        );
+        assert_eq!(trailers["grpc-status"], "0");
+
+        let (_, messages, trailers) = call(&state, "GetOrder", "missing").await;
+        assert!(messages.is_empty());
+        assert_eq!(trailers["grpc-status"], "5");
+        assert_eq!(trailers["grpc-message"], "order missing not found");
+    }
+
+    #[tokio::test]
+    async fn test_server_streaming_call_sends_every_message() {
+        let (_dir, state) = state().await;
+        let (_, messages, trailers) = call(&state, "WatchOrder", "B-2").await;
+        assert_eq!(
+            messages,
+            vec![
+                json!({ "id": "B-2", "quantity": 1 }),
+                json!({ "id": "B-2", "quantity": 2 })
+            ]
+        );
+        assert_eq!(trailers["grpc-status"], "0");
+        assert_eq!(state.app.journal.entries().len(), 1);
+    }
+
+    #[test]
+    fn test_inverted_stream_delays_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("shop.proto"), PROTO).unwrap();
+        let path = dir.path().join("endpoints.yaml");
+        let config = CONFIG.replace(
+            "- message: { id: \"{{body.id}}\", quantity: 2 }",
+            "- message: { id: \"{{body.id}}\", quantity: 2 }\n          delay: { min: 300, max: 100 }",
+        );
+        std::fs::write(&path, config).unwrap();
+
+        let err = crate::config::load_config(path.to_str().unwrap()).unwrap_err().to_string();
+        assert!(err.contains("shop.Orders/WatchOrder: stream: delay min (300)"), "{}", err);
+    }
+}
@@ -1,7 +1,13 @@
+mod cli;
 mod config;
+mod graphql;
+mod grpc;
 mod middleware;
+mod openapi;
+mod request;
 mod response;
 mod router;
+mod state;
 
 use axum::Router;
 use std::net::SocketAddr;
@@ -9,10 +15,22 @@ use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::ConfigManager;
+use crate::config::{watch_config, ConfigManager};
+use crate::state::AppState;
+
+/// The config file or directory, from `CONFIG_PATH`.
+fn config_path() -> String {
+    std::env::var("CONFIG_PATH").unwrap_or_else(|_| "config/endpoints.yaml".to_string())
+}
 
 #[tokio::main]
 async fn main() {
+    // Commands such as `import-openapi` run instead of the server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
@@ -22,19 +40,45 @@ async fn main() {
         .init();
 
     // Load configuration
-    let config_path = std::env::var("CONFIG_PATH")
-        .unwrap_or_else(|_| "config/endpoints.yaml".to_string());
+    let config_path = config_path();
     
     let config_manager = Arc::new(ConfigManager::new(&config_path).await
         .expect("Failed to load configuration"));
 
     let server_config = config_manager.get_config().server.clone();
 
+    // Reload the config whenever the file changes
+    if let Err(e) = watch_config(config_manager.clone()) {
+        tracing::warn!("Config hot reload disabled: {}", e);
+    }
+
+    let state = AppState::new(config_manager.clone());
+
+    // Serve gRPC mocks on their own port. Method mocks follow config
+    // reloads; the protos are only read at startup.
+    if let Some(grpc_config) = config_manager.get_config().grpc.clone() {
+        let descriptors = grpc::load_descriptors(&grpc_config)
+            .expect("Failed to load gRPC protos");
+        let grpc_addr = SocketAddr::new(
+            server_config.host.parse().expect("Invalid host"),
+            grpc_config.port,
+        );
+        let grpc_state = grpc::GrpcState {
+            app: state.clone(),
+            descriptors: Arc::new(descriptors),
+        };
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve_grpc(grpc_addr, grpc_state).await {
+                tracing::error!("gRPC server stopped: {}", e);
+            }
+        });
+    }
+
     // Build the router
     let app = Router::new()
         .fallback(crate::router::handle_dynamic_request)
         .layer(CorsLayer::permissive())
-        .with_state(config_manager);
+        .with_state(state);
 
     let addr = SocketAddr::new(
         server_config.host.parse().expect("Invalid host"),
@@ -45,5 +89,10 @@ async fn main() {
     tracing::info!("Config loaded from: {}", config_path);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -2,13 +2,14 @@ use rand::Rng;
 use tokio::time::{sleep, Duration};
 
 use crate::config::DelayConfig;
+use crate::request::RequestRng;
 
-pub async fn apply_delay(delay: &DelayConfig) {
+pub async fn apply_delay(delay: &DelayConfig, rng: &RequestRng) {
     let delay_ms = match delay {
         DelayConfig::Fixed(ms) => *ms,
+        // Loaded configs never invert a range, but a swapped one must not panic
         DelayConfig::Range { min, max, .. } => {
-            let mut rng = rand::thread_rng();
-            rng.gen_range(*min..=*max)
+            rng.with(|rng| rng.gen_range(*min.min(max)..=*min.max(max)))
         }
     };
 
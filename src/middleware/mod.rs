@@ -1,3 +1,5 @@
 mod delay;
+mod validation;
 
 pub use delay::*;
+pub use validation::*;
@@ -0,0 +1,185 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::config::{Pattern, ValidationConfig};
+use crate::request::RequestContext;
+
+/// A single failed rule, addressed by a JSON pointer into the request
+/// (`/params/<name>`, `/headers/<name>` or `/body/...`).
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(pointer: String, message: impl Into<String>) -> Self {
+        Self {
+            pointer,
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks a request against an endpoint's validation rules and returns every
+/// violation found. An empty list means the request is valid.
+pub fn validate_request(validation: &ValidationConfig, ctx: &RequestContext) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    let mut param_names: Vec<&String> = validation.params.keys().collect();
+    param_names.sort();
+    for name in param_names {
+        let rule = &validation.params[name];
+        let pointer = format!("/params/{}", escape_pointer(name));
+        match ctx.params.get(name) {
+            Some(value) => {
+                if let Some(pattern) = &rule.pattern {
+                    check_pattern(&mut violations, pointer, value, pattern);
+                }
+            }
+            None if rule.required => {
+                violations.push(Violation::new(pointer, "parameter is required"));
+            }
+            None => {}
+        }
+    }
+
+    let mut header_names: Vec<&String> = validation.headers.keys().collect();
+    header_names.sort();
+    for name in header_names {
+        let pattern = &validation.headers[name];
+        let pointer = format!("/headers/{}", escape_pointer(&name.to_lowercase()));
+        match ctx.headers.get(name.as_str()).map(|v| v.to_str()) {
+            Some(Ok(value)) => check_pattern(&mut violations, pointer, value, pattern),
+            Some(Err(_)) => {
+                violations.push(Violation::new(pointer, "header value is not valid UTF-8"));
+            }
+            None => violations.push(Violation::new(pointer, "header is required")),
+        }
+    }
+
+    if let Some(compiled) = validation.body_schema() {
+        match compiled {
+            Ok(compiled) => match &ctx.body {
+                Some(instance) => {
+                    if let Err(errors) = compiled.validate(instance) {
+                        for error in errors {
+                            violations.push(Violation::new(
+                                format!("/body{}", error.instance_path),
+                                error.to_string(),
+                            ));
+                        }
+                    }
+                }
+                None => violations.push(Violation::new(
+                    "/body".to_string(),
+                    "a JSON request body is required",
+                )),
+            },
+            Err(e) => {
+                tracing::error!("Invalid JSON Schema in endpoint validation: {}", e);
+                violations.push(Violation::new(
+                    "/body".to_string(),
+                    format!("endpoint schema is invalid: {}", e),
+                ));
+            }
+        }
+    }
+
+    violations
+}
+
+/// Builds the response body returned when a request fails validation.
+pub fn violations_body(violations: &[Violation]) -> Value {
+    json!({
+        "error": "Validation failed",
+        "violations": violations
+    })
+}
+
+fn check_pattern(violations: &mut Vec<Violation>, pointer: String, value: &str, pattern: &Pattern) {
+    if !pattern.is_match(value) {
+        violations.push(Violation::new(
+            pointer,
+            format!("value \"{}\" does not match pattern \"{}\"", value, pattern),
+        ));
+    }
+}
+
+/// Escapes a single reference token as described in RFC 6901.
+fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderMap;
+
+    fn config(yaml: &str) -> ValidationConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_param_pattern_and_required() {
+        let validation = config(
+            r#"
+params:
+  id: { pattern: "^[0-9]+$" }
+  org: { required: true }
+"#,
+        );
+        let mut ctx = RequestContext::default();
+        ctx.params.insert("id".to_string(), "abc".to_string());
+
+        let violations = validate_request(&validation, &ctx);
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["/params/id", "/params/org"]);
+    }
+
+    #[test]
+    fn test_header_rule() {
+        let mut validation = config("{}");
+        validation
+            .headers
+            .insert("X-Api-Key".to_string(), Pattern::new("^key-").unwrap());
+
+        let violations = validate_request(&validation, &RequestContext::default());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].pointer, "/headers/x-api-key");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "key-123".parse().unwrap());
+        let ctx = RequestContext {
+            headers,
+            ..Default::default()
+        };
+        assert!(validate_request(&validation, &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_body_schema_reports_every_violation() {
+        let validation = config(
+            r#"
+body:
+  type: object
+  required: [name]
+  properties:
+    name: { type: string }
+    address:
+      type: object
+      properties:
+        zip: { type: string, pattern: "^[0-9]{5}$" }
+"#,
+        );
+        let ctx = RequestContext {
+            body: Some(json!({ "address": { "zip": "abc" } })),
+            ..Default::default()
+        };
+
+        let violations = validate_request(&validation, &ctx);
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert!(pointers.contains(&"/body"));
+        assert!(pointers.contains(&"/body/address/zip"));
+    }
+}
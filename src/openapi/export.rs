@@ -0,0 +1,332 @@
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::config::{EndpointConfig, MockConfig, ResponseConfig, ResponseStatus, ValueMatch};
+use crate::request::RequestContext;
+use crate::response::{render_body, render_representation, RenderedBody};
+
+/// Renders the endpoints of a config as an OpenAPI 3.1 document. Response
+/// examples are produced by rendering each response once.
+pub fn export_openapi(config: &MockConfig) -> Value {
+    let mut paths = Map::new();
+
+    for endpoint in &config.endpoints {
+        for (path, method, operation) in operations(endpoint) {
+            let item = paths
+                .entry(openapi_path(&path))
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .expect("path items are objects");
+            match item.get_mut(&method) {
+                // Endpoints that differ only by query or scenario share one
+                // operation; later ones add the statuses it does not list yet
+                Some(existing) => merge_responses(existing, &operation),
+                None => {
+                    item.insert(method, operation);
+                }
+            }
+        }
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "MockServer",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": paths
+    })
+}
+
+/// `/users/:id` becomes `/users/{id}`.
+pub fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The operations an endpoint serves as `(path, method, operation)`. A
+/// resource serves its collection and item paths.
+fn operations(endpoint: &EndpointConfig) -> Vec<(String, String, Value)> {
+    let Some(resource) = &endpoint.resource else {
+        let method = endpoint.method.to_lowercase();
+        return vec![(endpoint.path.clone(), method, operation(endpoint))];
+    };
+
+    let item_path = format!("{}/:{}", endpoint.path.trim_end_matches('/'), resource.id_field);
+    let described = |path: &str, method: &str, status: &str, description: &str| {
+        let mut operation = json!({
+            "responses": { status: { "description": description } }
+        });
+        add_parameters(&mut operation, endpoint, path);
+        (path.to_string(), method.to_string(), operation)
+    };
+    vec![
+        described(&endpoint.path, "get", "200", "List items"),
+        described(&endpoint.path, "post", "201", "Create an item"),
+        described(&item_path, "get", "200", "Get an item"),
+        described(&item_path, "put", "200", "Replace an item"),
+        described(&item_path, "patch", "200", "Merge-patch an item"),
+        described(&item_path, "delete", "200", "Delete an item"),
+    ]
+}
+
+fn operation(endpoint: &EndpointConfig) -> Value {
+    let ctx = example_context(endpoint);
+    let mut responses = Map::new();
+
+    if endpoint.graphql.is_some() {
+        responses.insert(
+            "200".to_string(),
+            json!({
+                "description": "GraphQL response",
+                "content": { "application/json": { "schema": { "type": "object" } } }
+            }),
+        );
+    } else if endpoint.websocket.is_some() {
+        responses.insert(
+            "101".to_string(),
+            json!({ "description": "Switching to the WebSocket protocol" }),
+        );
+    } else if let Some(proxy) = &endpoint.proxy {
+        responses.insert(
+            "default".to_string(),
+            json!({ "description": format!("Proxied to {}", proxy.url()) }),
+        );
+    } else if endpoint.responses.is_empty() {
+        add_response(&mut responses, &endpoint.response, &ctx);
+    }
+    for entry in &endpoint.responses {
+        add_response(&mut responses, &entry.response, &ctx);
+    }
+    for condition in &endpoint.conditions {
+        add_response(&mut responses, &condition.response, &ctx);
+    }
+
+    let mut operation = json!({});
+    if let Some(validation) = &endpoint.validation {
+        if let Some(schema) = &validation.body {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": schema } }
+            });
+        }
+        responses
+            .entry(validation.status.to_string())
+            .or_insert_with(|| json!({ "description": "Validation failed" }));
+    }
+    operation["responses"] = Value::Object(responses);
+    add_parameters(&mut operation, endpoint, &endpoint.path);
+    operation
+}
+
+fn add_response(responses: &mut Map<String, Value>, response: &ResponseConfig, ctx: &RequestContext) {
+    let status = match &response.status {
+        ResponseStatus::Code(code) => code.to_string(),
+        ResponseStatus::Template(_) => "default".to_string(),
+    };
+    if responses.contains_key(&status) {
+        return;
+    }
+
+    let mut described = json!({ "description": description(&status) });
+    let header_type = response
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.render(ctx));
+    if response.sse.is_some() {
+        described["content"] = json!({ "text/event-stream": {} });
+        responses.insert(status, described);
+        return;
+    }
+    if !response.representations.is_empty() {
+        let mut content = Map::new();
+        for representation in &response.representations {
+            let mut media = json!({});
+            if let Ok(RenderedBody::Raw { bytes, .. }) =
+                render_representation(response, representation, ctx)
+            {
+                if let Ok(text) = std::str::from_utf8(&bytes) {
+                    media["example"] = json!(text);
+                }
+            }
+            content.insert(representation.media_type.clone(), media);
+        }
+        described["content"] = Value::Object(content);
+        responses.insert(status, described);
+        return;
+    }
+    match render_body(response, ctx) {
+        Ok(RenderedBody::Json(example)) if !example.is_null() => {
+            let content_type = header_type.unwrap_or_else(|| "application/json".to_string());
+            described["content"] = json!({ content_type: { "example": example } });
+        }
+        Ok(RenderedBody::Raw { content_type, bytes }) => {
+            let content_type = header_type.unwrap_or(content_type);
+            described["content"] = match std::str::from_utf8(&bytes) {
+                Ok(text) => json!({ content_type: { "example": text } }),
+                Err(_) => json!({ content_type: { "schema": { "type": "string", "format": "binary" } } }),
+            };
+        }
+        _ => {}
+    }
+    responses.insert(status, described);
+}
+
+/// Path parameters from `:param` segments (with their validation pattern)
+/// and query parameters from the endpoint's query matchers.
+fn add_parameters(operation: &mut Value, endpoint: &EndpointConfig, path: &str) {
+    let mut parameters = Vec::new();
+
+    for name in path.split('/').filter_map(|segment| segment.strip_prefix(':')) {
+        let mut schema = json!({ "type": "string" });
+        let pattern = endpoint
+            .validation
+            .as_ref()
+            .and_then(|v| v.params.get(name))
+            .and_then(|p| p.pattern.as_ref());
+        if let Some(pattern) = pattern {
+            schema["pattern"] = json!(pattern.to_string());
+        }
+        parameters.push(json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": schema
+        }));
+    }
+
+    for (name, expected) in &endpoint.query {
+        let mut schema = json!({ "type": "string" });
+        match expected {
+            ValueMatch::Exact(value) => schema["enum"] = json!([value]),
+            ValueMatch::Compare(comparison) => {
+                if let Some(pattern) = &comparison.matches {
+                    schema["pattern"] = json!(pattern.to_string());
+                }
+                if let Some(values) = &comparison.one_of {
+                    schema["enum"] = json!(values);
+                }
+            }
+        }
+        let required = !matches!(expected, ValueMatch::Compare(c) if c.exists == Some(false));
+        parameters.push(json!({
+            "name": name,
+            "in": "query",
+            "required": required,
+            "schema": schema
+        }));
+    }
+
+    if !parameters.is_empty() {
+        operation["parameters"] = Value::Array(parameters);
+    }
+}
+
+fn merge_responses(existing: &mut Value, operation: &Value) {
+    let (Some(existing), Some(new)) = (
+        existing.get_mut("responses").and_then(Value::as_object_mut),
+        operation.get("responses").and_then(Value::as_object),
+    ) else {
+        return;
+    };
+    for (status, response) in new {
+        existing.entry(status.clone()).or_insert_with(|| response.clone());
+    }
+}
+
+/// A request to render examples with: path parameters hold their own names.
+fn example_context(endpoint: &EndpointConfig) -> RequestContext {
+    let params: HashMap<String, String> = endpoint
+        .path
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix(':'))
+        .map(|name| (name.to_string(), name.to_string()))
+        .collect();
+    RequestContext {
+        method: endpoint.method.to_uppercase(),
+        path: endpoint.path.clone(),
+        params,
+        ..Default::default()
+    }
+}
+
+fn description(status: &str) -> String {
+    status
+        .parse::<u16>()
+        .ok()
+        .and_then(|code| axum::http::StatusCode::from_u16(code).ok())
+        .and_then(|code| code.canonical_reason())
+        .unwrap_or("Response")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_endpoints() {
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/users/:id"
+    validation:
+      params:
+        id: { pattern: "^[0-9]+$" }
+    response:
+      body:
+        id: { $param: "id" }
+        name: { $fake: "name" }
+  - path: "/users"
+    method: POST
+    validation:
+      status: 422
+      body: { type: object, required: [name] }
+    response:
+      status: 201
+      body: { created: true }
+"#,
+        )
+        .unwrap();
+
+        let doc = export_openapi(&config);
+        assert_eq!(doc["openapi"], "3.1.0");
+
+        let get = &doc["paths"]["/users/{id}"]["get"];
+        assert_eq!(get["parameters"][0]["name"], "id");
+        assert_eq!(get["parameters"][0]["schema"]["pattern"], "^[0-9]+$");
+        let example = &get["responses"]["200"]["content"]["application/json"]["example"];
+        assert_eq!(example["id"], "id");
+        assert!(example["name"].is_string());
+
+        let post = &doc["paths"]["/users"]["post"];
+        assert_eq!(
+            post["requestBody"]["content"]["application/json"]["schema"]["required"],
+            json!(["name"])
+        );
+        assert_eq!(post["responses"]["201"]["content"]["application/json"]["example"]["created"], true);
+        assert_eq!(post["responses"]["422"]["description"], "Validation failed");
+    }
+
+    #[test]
+    fn test_export_round_trips_through_import() {
+        let config: MockConfig = serde_yaml::from_str(
+            r#"
+endpoints:
+  - path: "/orders/:orderId"
+    response: { body: { id: 1 } }
+"#,
+        )
+        .unwrap();
+
+        let imported = super::super::openapi_to_config(&export_openapi(&config)).unwrap();
+        assert_eq!(imported.endpoints[0].path, "/orders/:orderId");
+        assert_eq!(imported.endpoints[0].response.body.value(), &json!({ "id": 1 }));
+    }
+}
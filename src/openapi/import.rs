@@ -0,0 +1,513 @@
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{
+    EndpointConfig, MockConfig, ParamValidation, Pattern, ResponseConfig, ResponseStatus,
+    ValidationConfig,
+};
+use crate::response::BodyTemplate;
+
+const METHODS: &[&str] = &["get", "put", "post", "delete", "patch", "head", "options", "trace"];
+
+/// How deep schemas are followed when synthesizing bodies; deeper (or
+/// recursive) parts become `null`.
+const MAX_DEPTH: usize = 8;
+
+/// Reads an OpenAPI 3.0/3.1 document (YAML or JSON) and turns each operation
+/// into an endpoint.
+pub fn import_openapi(path: &Path) -> Result<MockConfig, Box<dyn std::error::Error + Send + Sync>> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    // YAML is a superset of JSON, so one parser reads both
+    let doc: Value = serde_yaml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    openapi_to_config(&doc).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+/// Whether a YAML or JSON file is an OpenAPI document rather than a config.
+pub fn is_openapi_file(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<Value>(&content).ok())
+        .is_some_and(|doc| doc.get("openapi").is_some() && doc.get("paths").is_some())
+}
+
+pub fn openapi_to_config(doc: &Value) -> Result<MockConfig, String> {
+    let version = doc.get("openapi").and_then(Value::as_str).unwrap_or_default();
+    if !version.starts_with("3.") {
+        return Err(format!("unsupported OpenAPI version \"{}\", expected 3.x", version));
+    }
+
+    let spec = Spec { doc };
+    let mut endpoints = Vec::new();
+    let paths = doc.get("paths").and_then(Value::as_object).cloned().unwrap_or_default();
+
+    for (path, item) in &paths {
+        let item = spec.resolve(item);
+        let shared_params = item.get("parameters").and_then(Value::as_array);
+        for method in METHODS {
+            let Some(operation) = item.get(*method) else {
+                continue;
+            };
+            let operation = spec.resolve(operation);
+            let parameters: Vec<&Value> = shared_params
+                .into_iter()
+                .flatten()
+                .chain(operation.get("parameters").and_then(Value::as_array).into_iter().flatten())
+                .map(|p| spec.resolve(p))
+                .collect();
+
+            endpoints.push(EndpointConfig {
+                path: convert_path(path),
+                method: method.to_uppercase(),
+                response: spec.response(operation)?,
+                validation: spec.validation(operation, &parameters)?,
+                ..Default::default()
+            });
+        }
+    }
+
+    Ok(MockConfig {
+        endpoints,
+        ..Default::default()
+    })
+}
+
+/// `/users/{id}` becomes `/users/:id`.
+pub fn convert_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(name) => format!(":{}", name),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+struct Spec<'a> {
+    doc: &'a Value,
+}
+
+impl<'a> Spec<'a> {
+    /// Follows local `$ref`s (`#/components/...`) until a concrete node.
+    fn resolve(&self, node: &'a Value) -> &'a Value {
+        let mut node = node;
+        for _ in 0..MAX_DEPTH {
+            match node.get("$ref").and_then(Value::as_str) {
+                Some(reference) => match self.lookup(reference) {
+                    Some(target) => node = target,
+                    None => return node,
+                },
+                None => return node,
+            }
+        }
+        node
+    }
+
+    fn lookup(&self, reference: &str) -> Option<&'a Value> {
+        self.doc.pointer(reference.strip_prefix('#')?)
+    }
+
+    /// Picks the first success response (or `default`) and turns it into a
+    /// fixed response: its example when there is one, a synthesized template
+    /// otherwise.
+    fn response(&self, operation: &Value) -> Result<ResponseConfig, String> {
+        let responses = operation.get("responses").and_then(Value::as_object);
+        let mut candidates: Vec<(&String, &Value)> = responses.into_iter().flatten().collect();
+        candidates.sort_by_key(|(code, _)| match code.as_str() {
+            c if c.starts_with('2') => (0, c.to_string()),
+            "default" => (1, String::new()),
+            c => (2, c.to_string()),
+        });
+
+        let Some((code, response)) = candidates.first() else {
+            return Ok(ResponseConfig::default());
+        };
+        let response = self.resolve(response);
+        let status = match code.as_str() {
+            "default" => 200,
+            code => code.replace(['X', 'x'], "0").parse().unwrap_or(200),
+        };
+
+        let mut headers = HashMap::new();
+        let mut body = Value::Null;
+        if let Some((content_type, media)) = preferred_media(response) {
+            headers.insert(
+                "content-type".to_string(),
+                crate::response::Template::parse(content_type).map_err(|e| e.to_string())?,
+            );
+            body = self.example(media).unwrap_or_else(|| {
+                media
+                    .get("schema")
+                    .map(|schema| self.synthesize(schema, None, 0))
+                    .unwrap_or(Value::Null)
+            });
+        }
+
+        Ok(ResponseConfig {
+            status: ResponseStatus::Code(status),
+            headers,
+            body: BodyTemplate::new(body)?,
+            ..Default::default()
+        })
+    }
+
+    fn example(&self, media: &Value) -> Option<Value> {
+        if let Some(example) = media.get("example") {
+            return Some(example.clone());
+        }
+        let examples = media.get("examples").and_then(Value::as_object)?;
+        let first = self.resolve(examples.values().next()?);
+        first.get("value").cloned()
+    }
+
+    /// Maps the JSON request body schema into `validation.body` and path
+    /// parameter patterns into `validation.params`.
+    fn validation(
+        &self,
+        operation: &Value,
+        parameters: &[&Value],
+    ) -> Result<Option<ValidationConfig>, String> {
+        let body = operation
+            .get("requestBody")
+            .map(|b| self.resolve(b))
+            .and_then(|b| b.get("content"))
+            .and_then(|content| content.get("application/json"))
+            .and_then(|media| media.get("schema"))
+            .map(|schema| self.inline(schema, 0));
+
+        let mut params = HashMap::new();
+        for parameter in parameters {
+            if parameter.get("in").and_then(Value::as_str) != Some("path") {
+                continue;
+            }
+            let Some(name) = parameter.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let pattern = parameter
+                .get("schema")
+                .map(|s| self.resolve(s))
+                .and_then(|s| s.get("pattern"))
+                .and_then(Value::as_str)
+                .map(Pattern::new)
+                .transpose()
+                .map_err(|e| format!("parameter {}: {}", name, e))?;
+            if pattern.is_some() {
+                params.insert(name.to_string(), ParamValidation { pattern, required: true });
+            }
+        }
+
+        if body.is_none() && params.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(ValidationConfig {
+            params,
+            body,
+            ..Default::default()
+        }))
+    }
+
+    /// Copies a schema with every `$ref` replaced by its target, so it can be
+    /// compiled on its own.
+    fn inline(&self, schema: &Value, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            return json!({});
+        }
+        match self.resolve(schema) {
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.inline(value, depth + 1)))
+                    .collect(),
+            ),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|item| self.inline(item, depth + 1)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Builds a body template from a schema: `$fake` for scalars (guided by
+    /// `format` and the property name), `$array` for arrays.
+    fn synthesize(&self, schema: &Value, name: Option<&str>, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        let schema = self.resolve(schema);
+
+        if let Some(example) = schema.get("example") {
+            return example.clone();
+        }
+        if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|e| e.first()) {
+            return first.clone();
+        }
+        if let Some(constant) = schema.get("const") {
+            return constant.clone();
+        }
+        if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+            let mut merged = Map::new();
+            for part in parts {
+                if let Value::Object(fields) = self.synthesize(part, name, depth + 1) {
+                    merged.extend(fields);
+                }
+            }
+            return Value::Object(merged);
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(first) = schema.get(key).and_then(Value::as_array).and_then(|o| o.first()) {
+                return self.synthesize(first, name, depth + 1);
+            }
+        }
+
+        match schema_type(schema) {
+            "object" => {
+                let properties = schema.get("properties").and_then(Value::as_object);
+                Value::Object(
+                    properties
+                        .into_iter()
+                        .flatten()
+                        .map(|(key, property)| {
+                            (key.clone(), self.synthesize(property, Some(key), depth + 1))
+                        })
+                        .collect(),
+                )
+            }
+            "array" => {
+                let count = schema
+                    .get("minItems")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(3)
+                    .max(1);
+                let template = schema
+                    .get("items")
+                    .map(|items| self.synthesize(items, name, depth + 1))
+                    .unwrap_or(Value::Null);
+                json!({ "$array": { "count": count, "template": template } })
+            }
+            "integer" => {
+                let (min, max) = bounds(schema, 1.0);
+                let min = min.map(|min| min.ceil() as i64);
+                let max = max.map(|max| max.floor() as i64);
+                let (min, max) = match (min, max) {
+                    (Some(min), Some(max)) => (min, max.max(min)),
+                    (Some(min), None) => (min, min.saturating_add(1000)),
+                    (None, Some(max)) if max >= 1 => (1, max),
+                    (None, Some(max)) => (max.saturating_sub(1000), max),
+                    (None, None) => (1, 1001),
+                };
+                json!({ "$fake": "number", "min": min, "max": max })
+            }
+            "number" => {
+                // Fakes are rounded to two decimals, so exclusive bounds are
+                // kept a hundredth away
+                let (min, max) = match bounds(schema, 0.01) {
+                    (Some(min), Some(max)) => (min, max.max(min)),
+                    (Some(min), None) => (min, min + 1000.0),
+                    (None, Some(max)) if max >= 0.0 => (0.0, max),
+                    (None, Some(max)) => (max - 1000.0, max),
+                    (None, None) => (0.0, 1000.0),
+                };
+                json!({ "$fake": "float", "min": min, "max": max })
+            }
+            "boolean" => json!({ "$fake": "boolean" }),
+            "null" => Value::Null,
+            _ => json!({ "$fake": string_fake(schema, name) }),
+        }
+    }
+}
+
+fn preferred_media(response: &Value) -> Option<(&str, &Value)> {
+    let content = response.get("content").and_then(Value::as_object)?;
+    content
+        .get_key_value("application/json")
+        .or_else(|| content.iter().find(|(media_type, _)| media_type.contains("json")))
+        .or_else(|| content.iter().next())
+        .map(|(media_type, media)| (media_type.as_str(), media))
+}
+
+/// The schema's lower and upper bounds, `None` when unset. Exclusive
+/// bounds, the 3.0 boolean flags as well as the 3.1 numbers, are moved
+/// inwards by `step`.
+fn bounds(schema: &Value, step: f64) -> (Option<f64>, Option<f64>) {
+    let bound = |key: &str, exclusive_key: &str, step: f64| {
+        let inclusive = schema.get(key).and_then(Value::as_f64);
+        let exclusive = match schema.get(exclusive_key) {
+            Some(Value::Bool(true)) => inclusive.map(|bound| bound + step),
+            Some(bound) => bound.as_f64().map(|bound| bound + step),
+            None => None,
+        };
+        match (inclusive, exclusive) {
+            (Some(a), Some(b)) if step > 0.0 => Some(a.max(b)),
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => b.or(a),
+        }
+    };
+    (
+        bound("minimum", "exclusiveMinimum", step),
+        bound("maximum", "exclusiveMaximum", -step),
+    )
+}
+
+/// The schema's type; 3.1 type lists use their first non-null entry, and
+/// schemas with properties but no type are objects.
+fn schema_type(schema: &Value) -> &str {
+    match schema.get("type") {
+        Some(Value::String(t)) => t,
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "string",
+    }
+}
+
+/// Picks a fake data type for a string from its format, or failing that,
+/// from the property name.
+fn string_fake(schema: &Value, name: Option<&str>) -> &'static str {
+    match schema.get("format").and_then(Value::as_str) {
+        Some("uuid") => return "uuid",
+        Some("email") => return "email",
+        Some("date-time") | Some("date") => return "datetime",
+        Some("uri") | Some("url") => return "url",
+        Some("ipv4") => return "ipv4",
+        _ => {}
+    }
+
+    let name = name.unwrap_or_default().to_lowercase();
+    let by_name = [
+        ("email", "email"),
+        ("firstname", "firstname"),
+        ("first_name", "firstname"),
+        ("lastname", "lastname"),
+        ("last_name", "lastname"),
+        ("username", "username"),
+        ("phone", "phone"),
+        ("address", "address"),
+        ("city", "city"),
+        ("country", "country"),
+        ("zip", "zipcode"),
+        ("company", "company"),
+        ("url", "url"),
+        ("color", "color"),
+        ("title", "sentence"),
+        ("description", "paragraph"),
+        ("name", "name"),
+        ("id", "uuid"),
+    ];
+    by_name
+        .iter()
+        .find(|(fragment, _)| name.contains(fragment))
+        .map(|(_, fake)| *fake)
+        .unwrap_or("word")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestContext;
+    use crate::response::generate_response_body;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info: { title: Pets, version: "1" }
+paths:
+  /pets:
+    get:
+      responses:
+        "200":
+          description: ok
+          content:
+            application/json:
+              schema:
+                type: array
+                minItems: 2
+                items: { $ref: "#/components/schemas/Pet" }
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: "#/components/schemas/Pet" }
+      responses:
+        "201":
+          description: created
+          content:
+            application/json:
+              example: { id: 7, name: "Rex" }
+  /pets/{petId}:
+    parameters:
+      - { name: petId, in: path, required: true, schema: { type: string, pattern: "^[0-9]+$" } }
+    delete:
+      responses:
+        "204": { description: gone }
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      properties:
+        id: { type: integer, minimum: 1, maximum: 99 }
+        name: { type: string }
+        email: { type: string, format: email }
+"##;
+
+    #[test]
+    fn test_import_operations() {
+        let doc: Value = serde_yaml::from_str(SPEC).unwrap();
+        let config = openapi_to_config(&doc).unwrap();
+        let routes: Vec<(String, String)> = config
+            .endpoints
+            .iter()
+            .map(|e| (e.method.clone(), e.path.clone()))
+            .collect();
+        assert_eq!(
+            routes,
+            vec![
+                ("GET".to_string(), "/pets".to_string()),
+                ("POST".to_string(), "/pets".to_string()),
+                ("DELETE".to_string(), "/pets/:petId".to_string()),
+            ]
+        );
+
+        // Synthesized from the schema
+        let list = generate_response_body(&config.endpoints[0].response.body, &RequestContext::default());
+        let pets = list.as_array().unwrap();
+        assert_eq!(pets.len(), 2);
+        assert!(pets[0]["id"].as_i64().is_some_and(|id| (1..=99).contains(&id)));
+        assert!(pets[0]["email"].as_str().unwrap().contains('@'));
+
+        // Taken from the example, with the request schema inlined
+        let create = &config.endpoints[1];
+        assert_eq!(create.response.body.value(), &json!({ "id": 7, "name": "Rex" }));
+        let validation = create.validation.as_ref().unwrap();
+        assert_eq!(validation.body.as_ref().unwrap()["required"], json!(["name"]));
+
+        let delete = &config.endpoints[2];
+        assert!(matches!(delete.response.status, ResponseStatus::Code(204)));
+        assert!(delete.validation.as_ref().unwrap().params["petId"].pattern.is_some());
+    }
+
+    #[test]
+    fn test_synthesized_numbers_stay_within_bounds() {
+        let doc = json!({});
+        let spec = Spec { doc: &doc };
+        let fake = |schema: Value| {
+            let body = spec.synthesize(&schema, None, 0);
+            (body["min"].clone(), body["max"].clone())
+        };
+
+        assert_eq!(fake(json!({ "type": "integer", "maximum": 0 })), (json!(-1000), json!(0)));
+        assert_eq!(fake(json!({ "type": "integer", "maximum": 50 })), (json!(1), json!(50)));
+        assert_eq!(
+            fake(json!({ "type": "integer", "minimum": 0, "exclusiveMinimum": true, "exclusiveMaximum": 10 })),
+            (json!(1), json!(9))
+        );
+        assert_eq!(fake(json!({ "type": "number", "maximum": -5 })), (json!(-1005.0), json!(-5.0)));
+        assert_eq!(
+            fake(json!({ "type": "number", "minimum": 3, "maximum": 2 })),
+            (json!(3.0), json!(3.0))
+        );
+    }
+}
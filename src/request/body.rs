@@ -0,0 +1,188 @@
+use axum::http::{header, HeaderMap};
+use base64::Engine;
+use serde_json::{json, Map, Value};
+
+/// Parses a raw request body by its Content-Type into the value `$body`,
+/// conditions and validation see:
+///
+/// - JSON stays JSON.
+/// - Form fields become an object of strings (arrays for repeated fields).
+/// - Multipart parts become an object too: plain fields are strings, files
+///   are `{ filename, content_type, size }`.
+/// - Text (and anything else that is valid UTF-8) is kept as a string.
+/// - Binary bodies become a base64 string.
+pub fn parse_body(headers: &HeaderMap, bytes: &[u8]) -> Option<Value> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    if media_type == "application/x-www-form-urlencoded" {
+        return Some(parse_form(bytes));
+    }
+    if media_type == "multipart/form-data" {
+        if let Some(boundary) = media_param(content_type, "boundary") {
+            return Some(parse_multipart(bytes, &boundary));
+        }
+    }
+    if media_type.is_empty() || media_type == "application/json" || media_type.ends_with("+json") {
+        if let Ok(value) = serde_json::from_slice(bytes) {
+            return Some(value);
+        }
+    }
+
+    Some(match std::str::from_utf8(bytes) {
+        Ok(text) => Value::String(text.to_string()),
+        Err(_) => Value::String(base64::engine::general_purpose::STANDARD.encode(bytes)),
+    })
+}
+
+/// A parameter of a Content-Type value, such as its `boundary`.
+fn media_param(content_type: &str, name: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn parse_form(bytes: &[u8]) -> Value {
+    let mut fields = Map::new();
+    for (key, value) in form_urlencoded::parse(bytes) {
+        insert_field(&mut fields, key.into_owned(), Value::String(value.into_owned()));
+    }
+    Value::Object(fields)
+}
+
+fn parse_multipart(bytes: &[u8], boundary: &str) -> Value {
+    let delimiter = format!("--{}", boundary);
+    let mut fields = Map::new();
+
+    for part in split(bytes, delimiter.as_bytes()).into_iter().skip(1) {
+        // The closing delimiter is followed by `--`
+        if part.starts_with(b"--") {
+            break;
+        }
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        let Some(header_end) = find(part, b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&part[..header_end]);
+        let content = &part[header_end + 4..];
+
+        let mut disposition = "";
+        let mut part_type = None;
+        for line in head.lines() {
+            if let Some((name, value)) = line.split_once(':') {
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "content-disposition" => disposition = value.trim(),
+                    "content-type" => part_type = Some(value.trim().to_string()),
+                    _ => {}
+                }
+            }
+        }
+        let Some(name) = media_param(disposition, "name") else {
+            continue;
+        };
+
+        let value = match media_param(disposition, "filename") {
+            Some(filename) => json!({
+                "filename": filename,
+                "content_type": part_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+                "size": content.len()
+            }),
+            None => Value::String(String::from_utf8_lossy(content).into_owned()),
+        };
+        insert_field(&mut fields, name, value);
+    }
+
+    Value::Object(fields)
+}
+
+/// Adds a field, turning repeated names into arrays.
+fn insert_field(fields: &mut Map<String, Value>, name: String, value: Value) {
+    match fields.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => *existing = json!([existing.take(), value]),
+        None => {
+            fields.insert(name, value);
+        }
+    }
+}
+
+fn split<'a>(bytes: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = Vec::new();
+    let mut rest = bytes;
+    while let Some(index) = find(rest, delimiter) {
+        parts.push(&rest[..index]);
+        rest = &rest[index + delimiter.len()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(content_type: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        headers
+    }
+
+    #[test]
+    fn test_form_and_text_bodies() {
+        let form = parse_body(
+            &headers("application/x-www-form-urlencoded"),
+            b"name=Ana+Maria&tag=a&tag=b",
+        );
+        assert_eq!(form, Some(json!({ "name": "Ana Maria", "tag": ["a", "b"] })));
+
+        let xml = parse_body(&headers("application/xml"), b"<ping/>");
+        assert_eq!(xml, Some(json!("<ping/>")));
+
+        let binary = parse_body(&headers("application/octet-stream"), &[0xff, 0x00]);
+        assert_eq!(binary, Some(json!("/wA=")));
+
+        assert_eq!(parse_body(&headers("application/json"), b""), None);
+    }
+
+    #[test]
+    fn test_multipart_parts_carry_metadata() {
+        let body = b"--XyZ\r\n\
+Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+Holiday\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"photo\"; filename=\"beach.png\"\r\n\
+Content-Type: image/png\r\n\r\n\
+\x89PNG\r\n\x1a\n\r\n\
+--XyZ--\r\n";
+
+        let parsed = parse_body(&headers("multipart/form-data; boundary=XyZ"), body).unwrap();
+        assert_eq!(
+            parsed,
+            json!({
+                "title": "Holiday",
+                "photo": { "filename": "beach.png", "content_type": "image/png", "size": 8 }
+            })
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap, Method, Uri};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use super::{parse_body, RequestRng};
+
+/// Everything about an incoming request that conditions, validation and
+/// templates can look at.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    pub method: String,
+    pub path: String,
+    pub params: HashMap<String, String>,
+    pub query: HashMap<String, Vec<String>>,
+    pub headers: HeaderMap,
+    pub cookies: HashMap<String, String>,
+    /// The body parsed by its Content-Type (see `parse_body`).
+    pub body: Option<Value>,
+    /// The body exactly as received.
+    pub raw_body: Bytes,
+    /// Address of the connected client, when the server knows it.
+    pub client_ip: Option<IpAddr>,
+    /// Where fake values and random delays come from; seeded once the
+    /// endpoint is known (see `request_rng`).
+    pub rng: RequestRng,
+}
+
+impl RequestContext {
+    pub fn new(method: &Method, uri: &Uri, headers: HeaderMap, body: Option<Value>) -> Self {
+        Self {
+            method: method.as_str().to_string(),
+            path: uri.path().to_string(),
+            params: HashMap::new(),
+            query: parse_query(uri.query().unwrap_or("")),
+            cookies: parse_cookies(&headers),
+            headers,
+            body,
+            raw_body: Bytes::new(),
+            client_ip: None,
+            rng: RequestRng::default(),
+        }
+    }
+
+    /// Builds the context of an incoming request, parsing its raw body.
+    pub fn from_raw(method: &Method, uri: &Uri, headers: HeaderMap, raw_body: Bytes) -> Self {
+        let body = parse_body(&headers, &raw_body);
+        Self {
+            raw_body,
+            ..Self::new(method, uri, headers, body)
+        }
+    }
+
+    /// All values sent for a header, in order. Header names are case-insensitive.
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        self.headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    /// All values sent for a query key, in order.
+    pub fn query_values(&self, name: &str) -> &[String] {
+        self.query.get(name).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+}
+
+pub fn parse_query(query: &str) -> HashMap<String, Vec<String>> {
+    let mut result: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        result
+            .entry(key.into_owned())
+            .or_default()
+            .push(value.into_owned());
+    }
+    result
+}
+
+fn parse_cookies(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut cookies = HashMap::new();
+    for header_value in headers.get_all(header::COOKIE) {
+        let Ok(raw) = header_value.to_str() else {
+            continue;
+        };
+        for pair in raw.split(';') {
+            if let Some((name, value)) = pair.split_once('=') {
+                cookies.insert(name.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+    cookies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_and_cookies_are_parsed() {
+        let uri: Uri = "/search?q=foo+bar&tag=a&tag=b".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "session=abc123; theme=dark".parse().unwrap());
+
+        let ctx = RequestContext::new(&Method::GET, &uri, headers, None);
+        assert_eq!(ctx.path, "/search");
+        assert_eq!(ctx.query_values("q"), ["foo bar"]);
+        assert_eq!(ctx.query_values("tag"), ["a", "b"]);
+        assert_eq!(ctx.cookies.get("session").map(String::as_str), Some("abc123"));
+        assert_eq!(ctx.cookies.get("theme").map(String::as_str), Some("dark"));
+    }
+}
@@ -0,0 +1,7 @@
+mod body;
+mod context;
+mod rng;
+
+pub use body::*;
+pub use context::*;
+pub use rng::*;
@@ -0,0 +1,114 @@
+use parking_lot::Mutex;
+use rand::rngs::{StdRng, ThreadRng};
+use rand::{Rng, RngCore, SeedableRng};
+use std::fmt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// The random source behind a request's fake values, random delays and
+/// weighted picks. Seeded, the same request gets the same response every
+/// time; clones share one generator, so streamed messages keep drawing
+/// from the same sequence.
+#[derive(Clone)]
+pub struct RequestRng(Arc<Mutex<Generator>>);
+
+impl RequestRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(Generator {
+            rng: StdRng::seed_from_u64(seed),
+            seeded: true,
+        })
+    }
+
+    pub fn from_entropy() -> Self {
+        Self::new(Generator {
+            rng: StdRng::from_entropy(),
+            seeded: false,
+        })
+    }
+
+    fn new(generator: Generator) -> Self {
+        Self(Arc::new(Mutex::new(generator)))
+    }
+
+    /// Runs `f` with the generator locked. Calls must not nest.
+    pub fn with<T>(&self, f: impl FnOnce(&mut Generator) -> T) -> T {
+        f(&mut self.0.lock())
+    }
+
+    /// A version 4 UUID drawn from the generator.
+    pub fn uuid(&self) -> Uuid {
+        self.with(random_uuid)
+    }
+}
+
+impl Default for RequestRng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl fmt::Debug for RequestRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestRng")
+    }
+}
+
+/// A random source that tells whether it was seeded. Fakes that would
+/// otherwise follow the clock, such as datetimes, only repeat when it was.
+pub trait FakeRng: RngCore {
+    fn is_seeded(&self) -> bool;
+}
+
+/// The generator behind a `RequestRng`.
+pub struct Generator {
+    rng: StdRng,
+    seeded: bool,
+}
+
+impl RngCore for Generator {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+impl FakeRng for Generator {
+    fn is_seeded(&self) -> bool {
+        self.seeded
+    }
+}
+
+impl FakeRng for ThreadRng {
+    fn is_seeded(&self) -> bool {
+        false
+    }
+}
+
+pub fn random_uuid<R: Rng + ?Sized>(rng: &mut R) -> Uuid {
+    uuid::Builder::from_random_bytes(rng.gen()).into_uuid()
+}
+
+/// Folds strings into a seed with 64-bit FNV-1a, which unlike the std
+/// hasher is the same on every build.
+pub fn seed_from(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for byte in part.bytes().chain([0xff]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
@@ -0,0 +1,184 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+use base64::Engine;
+use serde_json::Value;
+use std::path::Path;
+
+use super::{convert_value, generate_response_body};
+use crate::config::{Representation, ResponseConfig};
+use crate::request::RequestContext;
+
+/// A response body ready to send: JSON, or raw bytes with their content type.
+pub enum RenderedBody {
+    Json(Value),
+    Raw { content_type: String, bytes: Bytes },
+}
+
+/// Renders the body a response config describes. Fails when a `bodyFile`
+/// cannot be read.
+pub fn render_body(response: &ResponseConfig, ctx: &RequestContext) -> Result<RenderedBody, String> {
+    let content_type = |default: &str| {
+        response
+            .content_type
+            .clone()
+            .unwrap_or_else(|| default.to_string())
+    };
+
+    if let Some(text) = &response.text {
+        return Ok(RenderedBody::Raw {
+            content_type: content_type("text/plain; charset=utf-8"),
+            bytes: Bytes::from(text.render(ctx)),
+        });
+    }
+    if let Some(file) = &response.body_file {
+        let bytes = std::fs::read(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        return Ok(RenderedBody::Raw {
+            content_type: content_type(content_type_for(file)),
+            bytes: Bytes::from(bytes),
+        });
+    }
+    if let Some(encoded) = &response.base64 {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("invalid base64 body: {}", e))?;
+        return Ok(RenderedBody::Raw {
+            content_type: content_type("application/octet-stream"),
+            bytes: Bytes::from(bytes),
+        });
+    }
+
+    let body = generate_response_body(&response.body, ctx);
+    match &response.content_type {
+        Some(content_type) => Ok(RenderedBody::Raw {
+            content_type: content_type.clone(),
+            bytes: Bytes::from(serde_json::to_vec(&body).unwrap_or_default()),
+        }),
+        None => Ok(RenderedBody::Json(body)),
+    }
+}
+
+/// Renders one of a response's representations: its own raw body if it has
+/// one, otherwise its (or the response's) JSON body converted to its type.
+pub fn render_representation(
+    response: &ResponseConfig,
+    representation: &Representation,
+    ctx: &RequestContext,
+) -> Result<RenderedBody, String> {
+    let media_type = &representation.media_type;
+    if representation.text.is_some()
+        || representation.body_file.is_some()
+        || representation.base64.is_some()
+    {
+        let raw = ResponseConfig {
+            text: representation.text.clone(),
+            body_file: representation.body_file.clone(),
+            base64: representation.base64.clone(),
+            content_type: Some(media_type.clone()),
+            ..Default::default()
+        };
+        return render_body(&raw, ctx);
+    }
+
+    let template = representation.body.as_ref().unwrap_or(&response.body);
+    let value = generate_response_body(template, ctx);
+    let converted = convert_value(&value, media_type)
+        .ok_or_else(|| format!("a JSON body cannot be converted to {}", media_type))?;
+    Ok(RenderedBody::Raw {
+        content_type: media_type.clone(),
+        bytes: Bytes::from(converted),
+    })
+}
+
+impl RenderedBody {
+    /// Builds the response. A Content-Type among `headers` takes precedence
+    /// over the body's own.
+    pub fn into_response(self, status: StatusCode, headers: HeaderMap) -> Response {
+        let (content_type, bytes) = match self {
+            RenderedBody::Json(value) => (
+                "application/json".to_string(),
+                Bytes::from(serde_json::to_vec(&value).unwrap_or_default()),
+            ),
+            RenderedBody::Raw { content_type, bytes } => (content_type, bytes),
+        };
+
+        let mut response = Response::new(Body::from(bytes));
+        *response.status_mut() = status;
+        if let Ok(value) = HeaderValue::from_str(&content_type) {
+            response.headers_mut().insert(header::CONTENT_TYPE, value);
+        }
+        response.headers_mut().extend(headers);
+        response
+    }
+}
+
+/// The Content-Type for a file, guessed from its extension.
+pub fn content_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "json" => "application/json",
+        "xml" | "wsdl" | "xsd" => "application/xml",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "txt" | "log" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "yaml" | "yml" => "application/yaml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_bodies() {
+        let response: ResponseConfig = serde_yaml::from_str(
+            r#"
+text: "<greeting>{{query.name}}</greeting>"
+content_type: "application/xml"
+"#,
+        )
+        .unwrap();
+        let uri = "/hello?name=Ana".parse().unwrap();
+        let ctx = RequestContext::new(&axum::http::Method::GET, &uri, HeaderMap::new(), None);
+        let RenderedBody::Raw { content_type, bytes } = render_body(&response, &ctx).unwrap() else {
+            panic!("expected a raw body");
+        };
+        assert_eq!(content_type, "application/xml");
+        assert_eq!(&bytes[..], b"<greeting>Ana</greeting>");
+
+        let binary: ResponseConfig = serde_yaml::from_str("base64: \"iVBORw==\"").unwrap();
+        let RenderedBody::Raw { content_type, bytes } = render_body(&binary, &ctx).unwrap() else {
+            panic!("expected a raw body");
+        };
+        assert_eq!(content_type, "application/octet-stream");
+        assert_eq!(&bytes[..], &[0x89, b'P', b'N', b'G']);
+    }
+
+    #[test]
+    fn test_content_type_from_extension() {
+        assert_eq!(content_type_for(Path::new("data/export.CSV")), "text/csv; charset=utf-8");
+        assert_eq!(content_type_for(Path::new("service.wsdl")), "application/xml");
+        assert_eq!(content_type_for(Path::new("blob")), "application/octet-stream");
+    }
+}
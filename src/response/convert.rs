@@ -0,0 +1,192 @@
+use serde_json::Value;
+
+use super::value_to_string;
+
+/// Media types a JSON body can be converted to automatically.
+pub fn is_convertible(media_type: &str) -> bool {
+    Format::of(media_type).is_some()
+}
+
+enum Format {
+    Json,
+    Xml,
+    Csv,
+    Yaml,
+    Text,
+}
+
+impl Format {
+    fn of(media_type: &str) -> Option<Self> {
+        let essence = media_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match essence.as_str() {
+            "application/json" => Some(Format::Json),
+            "application/xml" | "text/xml" => Some(Format::Xml),
+            "text/csv" => Some(Format::Csv),
+            "application/yaml" | "application/x-yaml" | "text/yaml" => Some(Format::Yaml),
+            "text/plain" => Some(Format::Text),
+            other if other.ends_with("+json") => Some(Format::Json),
+            other if other.ends_with("+xml") => Some(Format::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes a JSON value as `media_type`, or returns `None` when that type
+/// cannot be produced from JSON.
+pub fn convert_value(value: &Value, media_type: &str) -> Option<String> {
+    Some(match Format::of(media_type)? {
+        Format::Json => serde_json::to_string(value).unwrap_or_default(),
+        Format::Xml => to_xml(value),
+        Format::Csv => to_csv(value),
+        Format::Yaml => serde_yaml::to_string(value).unwrap_or_default(),
+        Format::Text => match value {
+            Value::String(text) => text.clone(),
+            other => serde_json::to_string_pretty(other).unwrap_or_default(),
+        },
+    })
+}
+
+/// Objects become child elements, arrays repeated `<item>` elements, all
+/// inside a `<response>` root.
+pub fn to_xml(value: &Value) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    write_element(&mut xml, "response", value);
+    xml
+}
+
+fn write_element(xml: &mut String, name: &str, value: &Value) {
+    let name = element_name(name);
+    match value {
+        Value::Null => xml.push_str(&format!("<{}/>", name)),
+        Value::Object(fields) => {
+            xml.push_str(&format!("<{}>", name));
+            for (key, field) in fields {
+                write_element(xml, key, field);
+            }
+            xml.push_str(&format!("</{}>", name));
+        }
+        Value::Array(items) => {
+            xml.push_str(&format!("<{}>", name));
+            for item in items {
+                write_element(xml, "item", item);
+            }
+            xml.push_str(&format!("</{}>", name));
+        }
+        scalar => xml.push_str(&format!(
+            "<{}>{}</{}>",
+            name,
+            escape_xml(&value_to_string(scalar)),
+            name
+        )),
+    }
+}
+
+/// Keeps letters, digits, `-`, `_` and `.`; names may not start with a digit.
+fn element_name(name: &str) -> String {
+    let mut clean: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+        .collect();
+    if !clean.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        clean.insert(0, '_');
+    }
+    clean
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// An array of objects becomes one row per object, with a header row of
+/// every key seen; a single object becomes one row. Nested values are
+/// written as JSON.
+pub fn to_csv(value: &Value) -> String {
+    let rows: Vec<&Value> = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+
+    let mut columns: Vec<String> = Vec::new();
+    for row in &rows {
+        match row {
+            Value::Object(fields) => {
+                for key in fields.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            _ if !columns.iter().any(|c| c == "value") => columns.push("value".to_string()),
+            _ => {}
+        }
+    }
+
+    let mut csv = csv_line(columns.iter().map(String::as_str));
+    for row in rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| match row {
+                Value::Object(fields) => fields.get(column).map(csv_cell).unwrap_or_default(),
+                scalar if column == "value" => csv_cell(scalar),
+                _ => String::new(),
+            })
+            .collect();
+        csv.push_str(&csv_line(cells.iter().map(String::as_str)));
+    }
+    csv
+}
+
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Object(_) | Value::Array(_) => value.to_string(),
+        scalar => value_to_string(scalar),
+    }
+}
+
+fn csv_line<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let cells: Vec<String> = cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect();
+    format!("{}\r\n", cells.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_xml_and_csv() {
+        let users = json!([
+            { "id": 1, "name": "Ana, Maria", "tags": ["a"] },
+            { "id": 2, "name": "Bruno" }
+        ]);
+
+        assert_eq!(
+            to_xml(&json!({ "users": users, "note": "a < b" })),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><response><note>a &lt; b</note>\
+<users><item><id>1</id><name>Ana, Maria</name><tags><item>a</item></tags></item>\
+<item><id>2</id><name>Bruno</name></item></users></response>"
+        );
+        assert_eq!(
+            to_csv(&users),
+            "id,name,tags\r\n1,\"Ana, Maria\",\"[\"\"a\"\"]\"\r\n2,Bruno,\r\n"
+        );
+        assert!(convert_value(&users, "image/png").is_none());
+    }
+}
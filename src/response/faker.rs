@@ -1,127 +1,133 @@
+use chrono::{DateTime, Utc};
 use fake::{Fake, faker};
 use rand::Rng;
 use serde_json::{json, Map, Value};
-use uuid::Uuid;
 
-pub fn generate_fake_value(fake_type: &str, config: &Map<String, Value>) -> Value {
+use crate::request::{random_uuid, FakeRng};
+
+/// Seeded fake datetimes fall in 2020-2025 rather than around the current
+/// time, so they are the same on every run.
+const DATETIME_RANGE: std::ops::Range<i64> = 1_577_836_800..1_767_225_600;
+
+pub fn generate_fake_value<R: FakeRng + ?Sized>(
+    fake_type: &str,
+    config: &Map<String, Value>,
+    rng: &mut R,
+) -> Value {
     match fake_type.to_lowercase().as_str() {
-        "uuid" => Value::String(Uuid::new_v4().to_string()),
+        "uuid" => Value::String(random_uuid(rng).to_string()),
         
         "name" | "fullname" | "name.fullname" => {
-            let name: String = faker::name::en::Name().fake();
+            let name: String = faker::name::en::Name().fake_with_rng(rng);
             Value::String(name)
         }
         
         "firstname" | "name.firstname" => {
-            let name: String = faker::name::en::FirstName().fake();
+            let name: String = faker::name::en::FirstName().fake_with_rng(rng);
             Value::String(name)
         }
         
         "lastname" | "name.lastname" => {
-            let name: String = faker::name::en::LastName().fake();
+            let name: String = faker::name::en::LastName().fake_with_rng(rng);
             Value::String(name)
         }
         
         "email" | "internet.email" => {
-            let email: String = faker::internet::en::SafeEmail().fake();
+            let email: String = faker::internet::en::SafeEmail().fake_with_rng(rng);
             Value::String(email)
         }
         
         "username" | "internet.username" => {
-            let username: String = faker::internet::en::Username().fake();
+            let username: String = faker::internet::en::Username().fake_with_rng(rng);
             Value::String(username)
         }
         
         "phone" | "phonenumber" => {
-            let phone: String = faker::phone_number::en::PhoneNumber().fake();
+            let phone: String = faker::phone_number::en::PhoneNumber().fake_with_rng(rng);
             Value::String(phone)
         }
         
         "address" | "address.full" => {
-            let street: String = faker::address::en::StreetName().fake();
-            let city: String = faker::address::en::CityName().fake();
-            let state: String = faker::address::en::StateName().fake();
+            let street: String = faker::address::en::StreetName().fake_with_rng(rng);
+            let city: String = faker::address::en::CityName().fake_with_rng(rng);
+            let state: String = faker::address::en::StateName().fake_with_rng(rng);
             Value::String(format!("{}, {}, {}", street, city, state))
         }
         
         "city" | "address.city" => {
-            let city: String = faker::address::en::CityName().fake();
+            let city: String = faker::address::en::CityName().fake_with_rng(rng);
             Value::String(city)
         }
         
         "country" | "address.country" => {
-            let country: String = faker::address::en::CountryName().fake();
+            let country: String = faker::address::en::CountryName().fake_with_rng(rng);
             Value::String(country)
         }
         
         "zipcode" | "address.zipcode" => {
-            let zip: String = faker::address::en::ZipCode().fake();
+            let zip: String = faker::address::en::ZipCode().fake_with_rng(rng);
             Value::String(zip)
         }
         
         "number" | "int" | "integer" => {
             let min = config.get("min").and_then(|v| v.as_i64()).unwrap_or(0);
             let max = config.get("max").and_then(|v| v.as_i64()).unwrap_or(100);
-            let mut rng = rand::thread_rng();
             Value::Number(rng.gen_range(min..=max).into())
         }
         
         "float" | "decimal" => {
             let min = config.get("min").and_then(|v| v.as_f64()).unwrap_or(0.0);
             let max = config.get("max").and_then(|v| v.as_f64()).unwrap_or(100.0);
-            let mut rng = rand::thread_rng();
             let val: f64 = rng.gen_range(min..=max);
             json!(format!("{:.2}", val).parse::<f64>().unwrap_or(val))
         }
         
         "bool" | "boolean" => {
-            let mut rng = rand::thread_rng();
             Value::Bool(rng.gen_bool(0.5))
         }
         
         "datetime" | "date" | "timestamp" => {
-            let now = chrono::Utc::now();
-            Value::String(now.to_rfc3339())
+            let datetime = if rng.is_seeded() { fake_datetime(rng) } else { Utc::now() };
+            Value::String(datetime.to_rfc3339())
         }
         
         "word" | "lorem.word" => {
-            let word: String = faker::lorem::en::Word().fake();
+            let word: String = faker::lorem::en::Word().fake_with_rng(rng);
             Value::String(word)
         }
         
         "sentence" | "lorem.sentence" => {
-            let sentence: String = faker::lorem::en::Sentence(3..8).fake();
+            let sentence: String = faker::lorem::en::Sentence(3..8).fake_with_rng(rng);
             Value::String(sentence)
         }
         
         "paragraph" | "lorem.paragraph" => {
-            let para: String = faker::lorem::en::Paragraph(2..5).fake();
+            let para: String = faker::lorem::en::Paragraph(2..5).fake_with_rng(rng);
             Value::String(para)
         }
         
         "company" | "company.name" => {
-            let company: String = faker::company::en::CompanyName().fake();
+            let company: String = faker::company::en::CompanyName().fake_with_rng(rng);
             Value::String(company)
         }
         
         "jobtitle" | "job.title" => {
-            let job: String = faker::job::en::Title().fake();
+            let job: String = faker::job::en::Title().fake_with_rng(rng);
             Value::String(job)
         }
         
         "url" | "internet.url" => {
-            let domain: String = faker::internet::en::DomainSuffix().fake();
-            let word: String = faker::lorem::en::Word().fake();
+            let domain: String = faker::internet::en::DomainSuffix().fake_with_rng(rng);
+            let word: String = faker::lorem::en::Word().fake_with_rng(rng);
             Value::String(format!("https://{}.{}", word, domain))
         }
         
         "ipv4" | "ip" => {
-            let ip: std::net::Ipv4Addr = faker::internet::en::IPv4().fake();
+            let ip: std::net::Ipv4Addr = faker::internet::en::IPv4().fake_with_rng(rng);
             Value::String(ip.to_string())
         }
         
         "color" | "color.hex" => {
-            let mut rng = rand::thread_rng();
             let r: u8 = rng.gen();
             let g: u8 = rng.gen();
             let b: u8 = rng.gen();
@@ -130,21 +136,34 @@ pub fn generate_fake_value(fake_type: &str, config: &Map<String, Value>) -> Valu
         
         _ => {
             // Default to a random string
-            let word: String = faker::lorem::en::Word().fake();
+            let word: String = faker::lorem::en::Word().fake_with_rng(rng);
             Value::String(word)
         }
     }
 }
 
+/// A random moment in the past year, or in `DATETIME_RANGE` when `rng` is
+/// seeded. Either way it is to the second.
+pub fn fake_datetime<R: FakeRng + ?Sized>(rng: &mut R) -> DateTime<Utc> {
+    let timestamp = if rng.is_seeded() {
+        rng.gen_range(DATETIME_RANGE)
+    } else {
+        Utc::now().timestamp() - rng.gen_range(0..365 * 24 * 3600)
+    };
+    DateTime::from_timestamp(timestamp, 0).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::request::RequestRng;
     use serde_json::Map;
+    use uuid::Uuid;
 
     #[test]
     fn test_uuid_generation() {
         let config = Map::new();
-        let result = generate_fake_value("uuid", &config);
+        let result = generate_fake_value("uuid", &config, &mut rand::thread_rng());
         assert!(result.is_string());
         let uuid_str = result.as_str().unwrap();
         assert!(Uuid::parse_str(uuid_str).is_ok());
@@ -156,9 +175,30 @@ mod tests {
         config.insert("min".to_string(), json!(10));
         config.insert("max".to_string(), json!(20));
         
-        let result = generate_fake_value("number", &config);
+        let result = generate_fake_value("number", &config, &mut rand::thread_rng());
         assert!(result.is_number());
         let num = result.as_i64().unwrap();
         assert!(num >= 10 && num <= 20);
     }
+
+    #[test]
+    fn test_same_seed_gives_same_values() {
+        let config = Map::new();
+        let fakes = |seed: u64| {
+            RequestRng::seeded(seed).with(|rng| {
+                ["uuid", "name", "email", "number", "datetime", "color"]
+                    .map(|fake_type| generate_fake_value(fake_type, &config, rng))
+            })
+        };
+        assert_eq!(fakes(7), fakes(7));
+        assert_ne!(fakes(7), fakes(8));
+    }
+
+    #[test]
+    fn test_unseeded_datetime_is_now() {
+        let before = Utc::now().timestamp();
+        let result = generate_fake_value("datetime", &Map::new(), &mut rand::thread_rng());
+        let datetime = DateTime::parse_from_rfc3339(result.as_str().unwrap()).unwrap();
+        assert!(datetime.timestamp() >= before);
+    }
 }
@@ -1,42 +1,67 @@
 use serde_json::{json, Map, Value};
-use std::collections::HashMap;
 
 use super::faker::generate_fake_value;
+use super::schema::generate_from_schema;
+use super::template::BodyTemplate;
+use crate::request::RequestContext;
 
-pub fn generate_response_body(
-    template: &Value,
-    params: &HashMap<String, String>,
-    request_body: Option<&Value>,
-) -> Value {
-    process_value(template, params, request_body)
+pub fn generate_response_body(template: &BodyTemplate, ctx: &RequestContext) -> Value {
+    process_value(template.value(), template, ctx)
 }
 
-fn process_value(
-    value: &Value,
-    params: &HashMap<String, String>,
-    request_body: Option<&Value>,
-) -> Value {
+fn process_value(value: &Value, body: &BodyTemplate, ctx: &RequestContext) -> Value {
     match value {
         Value::Object(obj) => {
             // Check for special directives
             if let Some(fake_type) = obj.get("$fake") {
-                return generate_fake_value(fake_type.as_str().unwrap_or("string"), obj);
+                let fake_type = fake_type.as_str().unwrap_or("string");
+                return ctx.rng.with(|rng| generate_fake_value(fake_type, obj, rng));
+            }
+
+            if let Some(schema) = obj.get("$schema").filter(|s| s.is_object()) {
+                return ctx.rng.with(|rng| generate_from_schema(schema, rng));
             }
 
             if let Some(param_name) = obj.get("$param") {
                 if let Some(name) = param_name.as_str() {
-                    if let Some(param_value) = params.get(name) {
+                    if let Some(param_value) = ctx.params.get(name) {
                         return Value::String(param_value.clone());
                     }
                 }
                 return Value::Null;
             }
 
+            if let Some(query_key) = obj.get("$query") {
+                // Repeated keys (`?tag=a&tag=b`) come back as an array
+                return match query_key.as_str().and_then(|key| ctx.query.get(key)) {
+                    Some(values) if values.len() == 1 => Value::String(values[0].clone()),
+                    Some(values) => json!(values),
+                    None => Value::Null,
+                };
+            }
+
+            if let Some(header_name) = obj.get("$header") {
+                return header_name
+                    .as_str()
+                    .and_then(|name| ctx.headers.get(name))
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| Value::String(v.to_string()))
+                    .unwrap_or(Value::Null);
+            }
+
+            if let Some(cookie_name) = obj.get("$cookie") {
+                return cookie_name
+                    .as_str()
+                    .and_then(|name| ctx.cookies.get(name))
+                    .map(|v| Value::String(v.clone()))
+                    .unwrap_or(Value::Null);
+            }
+
             if let Some(body_field) = obj.get("$body") {
-                if let Some(field_name) = body_field.as_str() {
-                    if let Some(body) = request_body {
-                        if let Some(field_value) = body.get(field_name) {
-                            return field_value.clone();
+                if let Some(path) = body_field.as_str().and_then(|p| body.path(p)) {
+                    if let Some(request_body) = &ctx.body {
+                        if let Some(field_value) = path.resolve(request_body) {
+                            return field_value;
                         }
                     }
                 }
@@ -44,7 +69,7 @@ fn process_value(
             }
 
             if let Some(array_config) = obj.get("$array") {
-                return generate_array(array_config, obj, params, request_body);
+                return generate_array(array_config, obj, body, ctx);
             }
 
             // Regular object - process all fields
@@ -53,13 +78,17 @@ fn process_value(
                 if key.starts_with('$') && key != "$array" {
                     continue;
                 }
-                result.insert(key.clone(), process_value(val, params, request_body));
+                result.insert(key.clone(), process_value(val, body, ctx));
             }
             Value::Object(result)
         }
         Value::Array(arr) => {
-            Value::Array(arr.iter().map(|v| process_value(v, params, request_body)).collect())
+            Value::Array(arr.iter().map(|v| process_value(v, body, ctx)).collect())
         }
+        Value::String(s) => match body.template(s) {
+            Some(template) => template.render_value(ctx),
+            None => value.clone(),
+        },
         _ => value.clone(),
     }
 }
@@ -67,8 +96,8 @@ fn process_value(
 fn generate_array(
     config: &Value,
     _obj: &Map<String, Value>,
-    params: &HashMap<String, String>,
-    request_body: Option<&Value>,
+    body: &BodyTemplate,
+    ctx: &RequestContext,
 ) -> Value {
     let config_obj = config.as_object();
     
@@ -83,7 +112,7 @@ fn generate_array(
         .unwrap_or(&default_template);
 
     let items: Vec<Value> = (0..count)
-        .map(|_| process_value(template, params, request_body))
+        .map(|_| process_value(template, body, ctx))
         .collect();
 
     Value::Array(items)
@@ -92,16 +121,17 @@ fn generate_array(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::http::{header, HeaderMap, Method, Uri};
 
     #[test]
     fn test_process_param() {
         let template = json!({
             "id": { "$param": "userId" }
         });
-        let mut params = HashMap::new();
-        params.insert("userId".to_string(), "123".to_string());
+        let mut ctx = RequestContext::default();
+        ctx.params.insert("userId".to_string(), "123".to_string());
         
-        let result = generate_response_body(&template, &params, None);
+        let result = generate_response_body(&template.into(), &ctx);
         assert_eq!(result["id"], "123");
     }
 
@@ -110,10 +140,86 @@ mod tests {
         let template = json!({
             "name": { "$body": "userName" }
         });
-        let params = HashMap::new();
-        let body = json!({ "userName": "John" });
+        let ctx = RequestContext {
+            body: Some(json!({ "userName": "John" })),
+            ..Default::default()
+        };
         
-        let result = generate_response_body(&template, &params, Some(&body));
+        let result = generate_response_body(&template.into(), &ctx);
         assert_eq!(result["name"], "John");
     }
+
+    #[test]
+    fn test_process_nested_body_paths() {
+        let template = json!({
+            "city": { "$body": "user.address.city" },
+            "firstId": { "$body": "items[0].id" },
+            "zip": { "$body": "/user/address/zip" },
+            "ids": { "$body": "$.items[*].id" }
+        });
+        let ctx = RequestContext {
+            body: Some(json!({
+                "user": { "address": { "city": "Porto", "zip": "4000" } },
+                "items": [{ "id": "a" }, { "id": "b" }]
+            })),
+            ..Default::default()
+        };
+
+        let result = generate_response_body(&template.into(), &ctx);
+        assert_eq!(result["city"], "Porto");
+        assert_eq!(result["firstId"], "a");
+        assert_eq!(result["zip"], "4000");
+        assert_eq!(result["ids"], json!(["a", "b"]));
+
+        let bad = json!({ "sku": { "$body": "$.items[?(@.sku =~ /[a-/)]" } });
+        assert!(BodyTemplate::new(bad).is_err());
+    }
+
+    #[test]
+    fn test_process_interpolated_strings() {
+        let template = json!({
+            "greeting": "Hello {{param.id}}",
+            "items": { "$array": { "count": 2, "template": "{{query.prefix | default 'item'}}" } }
+        });
+        let mut ctx = RequestContext::default();
+        ctx.params.insert("id".to_string(), "7".to_string());
+
+        let result = generate_response_body(&template.into(), &ctx);
+        assert_eq!(result["greeting"], "Hello 7");
+        assert_eq!(result["items"], json!(["item", "item"]));
+    }
+
+    #[test]
+    fn test_process_query() {
+        let template = json!({
+            "page": { "$query": "page" },
+            "tags": { "$query": "tag" },
+            "missing": { "$query": "q" }
+        });
+        let uri: Uri = "/search?page=2&tag=a&tag=b".parse().unwrap();
+        let ctx = RequestContext::new(&Method::GET, &uri, HeaderMap::new(), None);
+
+        let result = generate_response_body(&template.into(), &ctx);
+        assert_eq!(result["page"], "2");
+        assert_eq!(result["tags"], json!(["a", "b"]));
+        assert_eq!(result["missing"], Value::Null);
+    }
+
+    #[test]
+    fn test_process_header_and_cookie() {
+        let template = json!({
+            "requestId": { "$header": "X-Request-Id" },
+            "session": { "$cookie": "session" },
+            "missing": { "$header": "X-Missing" }
+        });
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req-42".parse().unwrap());
+        headers.insert(header::COOKIE, "session=abc; theme=dark".parse().unwrap());
+        let ctx = RequestContext::new(&Method::GET, &"/".parse().unwrap(), headers, None);
+
+        let result = generate_response_body(&template.into(), &ctx);
+        assert_eq!(result["requestId"], "req-42");
+        assert_eq!(result["session"], "abc");
+        assert_eq!(result["missing"], Value::Null);
+    }
 }
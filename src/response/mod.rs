@@ -1,4 +1,16 @@
+mod body;
+mod convert;
 mod generator;
 mod faker;
+mod negotiation;
+mod path;
+mod schema;
+mod template;
 
+pub use body::*;
+pub use convert::*;
+pub use faker::*;
 pub use generator::*;
+pub use negotiation::*;
+pub use path::*;
+pub use template::*;
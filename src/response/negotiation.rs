@@ -0,0 +1,83 @@
+/// Picks which of the `offered` media types best satisfies an `Accept`
+/// header, honouring q-values and preferring earlier offers on ties.
+/// Returns `None` when nothing offered is acceptable. Without an `Accept`
+/// header the first offer wins.
+pub fn negotiate(accept: Option<&str>, offered: &[&str]) -> Option<usize> {
+    let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+        return (!offered.is_empty()).then_some(0);
+    };
+    let ranges: Vec<MediaRange> = accept.split(',').filter_map(MediaRange::parse).collect();
+
+    let mut best: Option<(usize, f32)> = None;
+    for (index, media_type) in offered.iter().enumerate() {
+        let Some((kind, subtype)) = essence(media_type) else {
+            continue;
+        };
+        // The most specific range that matches decides the quality
+        let quality = ranges
+            .iter()
+            .filter(|range| range.matches(&kind, &subtype))
+            .max_by_key(|range| range.specificity())
+            .map(|range| range.quality)
+            .unwrap_or(0.0);
+        if quality > 0.0 && best.is_none_or(|(_, q)| quality > q) {
+            best = Some((index, quality));
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+struct MediaRange {
+    kind: String,
+    subtype: String,
+    quality: f32,
+}
+
+impl MediaRange {
+    fn parse(range: &str) -> Option<Self> {
+        let mut parts = range.split(';');
+        let (kind, subtype) = essence(parts.next()?)?;
+        let quality = parts
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+            .and_then(|(_, value)| value.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        Some(Self {
+            kind,
+            subtype,
+            quality,
+        })
+    }
+
+    fn matches(&self, kind: &str, subtype: &str) -> bool {
+        (self.kind == "*" || self.kind == kind) && (self.subtype == "*" || self.subtype == subtype)
+    }
+
+    fn specificity(&self) -> u8 {
+        (self.kind != "*") as u8 + (self.subtype != "*") as u8
+    }
+}
+
+/// `type/subtype` of a media type, lowercased and without parameters.
+fn essence(media_type: &str) -> Option<(String, String)> {
+    let essence = media_type.split(';').next()?.trim().to_ascii_lowercase();
+    let (kind, subtype) = essence.split_once('/')?;
+    Some((kind.to_string(), subtype.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_with_q_values() {
+        let offered = ["application/json", "application/xml", "text/csv"];
+
+        assert_eq!(negotiate(None, &offered), Some(0));
+        assert_eq!(negotiate(Some("text/csv"), &offered), Some(2));
+        assert_eq!(negotiate(Some("application/json;q=0.5, application/xml"), &offered), Some(1));
+        assert_eq!(negotiate(Some("text/*;q=0.9, */*;q=0.1"), &offered), Some(2));
+        assert_eq!(negotiate(Some("*/*, application/json;q=0"), &offered), Some(1));
+        assert_eq!(negotiate(Some("image/png"), &offered), None);
+    }
+}
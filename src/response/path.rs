@@ -0,0 +1,559 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A path into a JSON document, parsed when the config holding it is loaded
+/// and resolved per request.
+///
+/// Three path syntaxes are accepted:
+/// - JSON Pointer (RFC 6901) when the path starts with `/`: `/user/address/city`
+/// - dotted paths with array indices: `user.address.city`, `items[0].id`, `items.0.id`
+/// - a JSONPath subset when the path starts with `$`: `$.items[*].id`,
+///   `$..name`, `$.items[-1]`, `$.items[0:2]`, `$.items[?(@.price > 10)]`
+///
+/// An empty path is the whole document.
+#[derive(Clone)]
+pub struct ValuePath {
+    source: String,
+    /// `None` for JSON Pointers, which `serde_json` resolves itself.
+    query: Option<JsonPath>,
+}
+
+impl ValuePath {
+    /// Parses a path. A JSONPath filter's `=~` regex must compile.
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let query = if path.is_empty() || path.starts_with('/') {
+            None
+        } else {
+            Some(JsonPath::parse(path).ok_or_else(|| format!("invalid path \"{}\"", path))?)
+        };
+        Ok(Self {
+            source: path.to_string(),
+            query,
+        })
+    }
+
+    /// Paths that can only ever select one value return that value. Paths
+    /// with wildcards, slices, unions, filters or recursive descent return
+    /// an array of every match.
+    pub fn resolve(&self, value: &Value) -> Option<Value> {
+        let Some(query) = &self.query else {
+            return value.pointer(&self.source).cloned();
+        };
+        let matches = query.select(value);
+        if query.is_definite() {
+            matches.into_iter().next().cloned()
+        } else {
+            Some(Value::Array(matches.into_iter().cloned().collect()))
+        }
+    }
+
+    /// Like [`ValuePath::resolve`], but returns every matched value
+    /// separately.
+    pub fn resolve_all<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        match &self.query {
+            Some(query) => query.select(value),
+            None => value.pointer(&self.source).into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Debug for ValuePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ValuePath({:?})", self.source)
+    }
+}
+
+impl Serialize for ValuePath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for ValuePath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        ValuePath::parse(&path).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Renders a JSON value as the plain string used for comparisons: strings
+/// are returned unquoted, everything else as compact JSON.
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Clone)]
+struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone)]
+struct Segment {
+    recursive: bool,
+    selector: Selector,
+}
+
+#[derive(Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Union(Vec<Selector>),
+    Filter(Filter),
+}
+
+/// A filter expression in disjunctive form: `a && b || c`.
+#[derive(Clone)]
+struct Filter {
+    any_of: Vec<Vec<FilterTest>>,
+}
+
+#[derive(Clone)]
+struct FilterTest {
+    path: JsonPath,
+    comparison: Option<Comparison>,
+}
+
+/// What a filter test compares matched values with. Regexes are compiled
+/// when the path is parsed.
+#[derive(Clone)]
+enum Comparison {
+    Value(FilterOp, Value),
+    Matches(Regex),
+}
+
+#[derive(Clone, Copy)]
+enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl JsonPath {
+    fn parse(path: &str) -> Option<Self> {
+        // Dotted paths are JSONPath without the leading `$`
+        let normalized = if let Some(rest) = path.strip_prefix('$') {
+            rest.to_string()
+        } else if path.starts_with('[') {
+            path.to_string()
+        } else {
+            format!(".{}", path)
+        };
+
+        let chars: Vec<char> = normalized.chars().collect();
+        let mut segments = Vec::new();
+        let mut pos = 0;
+
+        while pos < chars.len() {
+            let recursive = chars[pos] == '.' && chars.get(pos + 1) == Some(&'.');
+            let selector = if recursive || chars[pos] == '.' {
+                pos += if recursive { 2 } else { 1 };
+                if chars.get(pos) == Some(&'[') {
+                    if !recursive {
+                        return None;
+                    }
+                    parse_bracket(&chars, &mut pos)?
+                } else if chars.get(pos) == Some(&'*') {
+                    pos += 1;
+                    Selector::Wildcard
+                } else {
+                    let start = pos;
+                    while pos < chars.len() && chars[pos] != '.' && chars[pos] != '[' {
+                        pos += 1;
+                    }
+                    if start == pos {
+                        return None;
+                    }
+                    Selector::Name(chars[start..pos].iter().collect())
+                }
+            } else if chars[pos] == '[' {
+                parse_bracket(&chars, &mut pos)?
+            } else {
+                return None;
+            };
+            segments.push(Segment {
+                recursive,
+                selector,
+            });
+        }
+
+        Some(Self { segments })
+    }
+
+    fn is_definite(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            !segment.recursive && matches!(segment.selector, Selector::Name(_) | Selector::Index(_))
+        })
+    }
+
+    fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in current {
+                if segment.recursive {
+                    let mut nodes = Vec::new();
+                    collect_descendants(value, &mut nodes);
+                    for node in nodes {
+                        segment.selector.apply(node, &mut next);
+                    }
+                } else {
+                    segment.selector.apply(value, &mut next);
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+impl Selector {
+    fn apply<'a>(&self, value: &'a Value, out: &mut Vec<&'a Value>) {
+        match self {
+            Selector::Name(name) => match value {
+                Value::Object(map) => out.extend(map.get(name)),
+                Value::Array(items) => {
+                    if let Ok(index) = name.parse::<usize>() {
+                        out.extend(items.get(index));
+                    }
+                }
+                _ => {}
+            },
+            Selector::Wildcard => out.extend(children(value)),
+            Selector::Index(index) => {
+                if let Value::Array(items) = value {
+                    let len = items.len() as i64;
+                    let index = if *index < 0 { len + index } else { *index };
+                    if (0..len).contains(&index) {
+                        out.push(&items[index as usize]);
+                    }
+                }
+            }
+            Selector::Slice(start, end) => {
+                if let Value::Array(items) = value {
+                    let len = items.len() as i64;
+                    let clamp = |i: i64| (if i < 0 { len + i } else { i }).clamp(0, len) as usize;
+                    let start = start.map(clamp).unwrap_or(0);
+                    let end = end.map(clamp).unwrap_or(items.len());
+                    if start < end {
+                        out.extend(&items[start..end]);
+                    }
+                }
+            }
+            Selector::Union(selectors) => {
+                for selector in selectors {
+                    selector.apply(value, out);
+                }
+            }
+            Selector::Filter(filter) => {
+                out.extend(children(value).filter(|child| filter.matches(child)));
+            }
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, candidate: &Value) -> bool {
+        self.any_of
+            .iter()
+            .any(|all_of| all_of.iter().all(|test| test.matches(candidate)))
+    }
+}
+
+impl FilterTest {
+    fn matches(&self, candidate: &Value) -> bool {
+        let values = self.path.select(candidate);
+        match &self.comparison {
+            None => !values.is_empty(),
+            Some(Comparison::Value(op, expected)) => {
+                values.iter().any(|actual| compare(actual, *op, expected))
+            }
+            Some(Comparison::Matches(regex)) => values
+                .iter()
+                .any(|actual| actual.as_str().is_some_and(|text| regex.is_match(text))),
+        }
+    }
+}
+
+fn compare(actual: &Value, op: FilterOp, expected: &Value) -> bool {
+    let ordering = match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (a, b) if a == b => Some(Ordering::Equal),
+        _ => None,
+    };
+
+    match op {
+        FilterOp::Eq => ordering == Some(Ordering::Equal),
+        FilterOp::Ne => ordering != Some(Ordering::Equal),
+        FilterOp::Lt => ordering == Some(Ordering::Less),
+        FilterOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        FilterOp::Gt => ordering == Some(Ordering::Greater),
+        FilterOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+fn children(value: &Value) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Value::Object(map) => Box::new(map.values()),
+        Value::Array(items) => Box::new(items.iter()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn collect_descendants<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(value);
+    for child in children(value) {
+        collect_descendants(child, out);
+    }
+}
+
+/// Parses a `[...]` selector starting at `pos` and moves `pos` past it.
+fn parse_bracket(chars: &[char], pos: &mut usize) -> Option<Selector> {
+    let start = *pos + 1;
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut end = None;
+    for (i, &c) in chars.iter().enumerate().skip(*pos) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+    *pos = end + 1;
+
+    let content: String = chars[start..end].iter().collect();
+    parse_selector(content.trim())
+}
+
+fn parse_selector(content: &str) -> Option<Selector> {
+    if content == "*" {
+        return Some(Selector::Wildcard);
+    }
+    if let Some(expr) = content.strip_prefix('?') {
+        let expr = expr.trim();
+        let expr = expr
+            .strip_prefix('(')
+            .and_then(|e| e.strip_suffix(')'))
+            .unwrap_or(expr);
+        return parse_filter(expr).map(Selector::Filter);
+    }
+
+    let parts = split_outside_quotes(content, ",");
+    if parts.len() > 1 {
+        return parts
+            .iter()
+            .map(|part| parse_selector(part.trim()))
+            .collect::<Option<Vec<_>>>()
+            .map(Selector::Union);
+    }
+
+    if let Some(name) = unquote(content) {
+        return Some(Selector::Name(name));
+    }
+    if let Some((start, end)) = content.split_once(':') {
+        let bound = |s: &str| -> Option<Option<i64>> {
+            let s = s.trim();
+            if s.is_empty() {
+                Some(None)
+            } else {
+                s.parse().ok().map(Some)
+            }
+        };
+        return Some(Selector::Slice(bound(start)?, bound(end)?));
+    }
+    content.parse().ok().map(Selector::Index)
+}
+
+fn parse_filter(expr: &str) -> Option<Filter> {
+    let any_of = split_outside_quotes(expr, "||")
+        .iter()
+        .map(|alternative| {
+            split_outside_quotes(alternative, "&&")
+                .iter()
+                .map(|test| parse_filter_test(test.trim()))
+                .collect::<Option<Vec<_>>>()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Filter { any_of })
+}
+
+fn parse_filter_test(test: &str) -> Option<FilterTest> {
+    enum Operator {
+        Compare(FilterOp),
+        Matches,
+    }
+    const OPERATORS: [(&str, Operator); 7] = [
+        ("==", Operator::Compare(FilterOp::Eq)),
+        ("!=", Operator::Compare(FilterOp::Ne)),
+        ("<=", Operator::Compare(FilterOp::Le)),
+        (">=", Operator::Compare(FilterOp::Ge)),
+        ("=~", Operator::Matches),
+        ("<", Operator::Compare(FilterOp::Lt)),
+        (">", Operator::Compare(FilterOp::Gt)),
+    ];
+
+    let operator = OPERATORS.iter().find_map(|(token, operator)| {
+        let parts = split_outside_quotes(test, token);
+        (parts.len() == 2).then(|| (parts[0].trim(), operator, parts[1].trim()))
+    });
+
+    let (left, comparison) = match operator {
+        Some((left, Operator::Matches, right)) => {
+            let regex = Regex::new(parse_literal(right)?.as_str()?).ok()?;
+            (left, Some(Comparison::Matches(regex)))
+        }
+        Some((left, Operator::Compare(op), right)) => {
+            (left, Some(Comparison::Value(*op, parse_literal(right)?)))
+        }
+        None => (test, None),
+    };
+
+    let relative = left.strip_prefix('@')?;
+    let path = if relative.is_empty() {
+        JsonPath {
+            segments: Vec::new(),
+        }
+    } else {
+        JsonPath::parse(&format!("${}", relative))?
+    };
+
+    Some(FilterTest { path, comparison })
+}
+
+fn parse_literal(literal: &str) -> Option<Value> {
+    if let Some(text) = unquote(literal) {
+        return Some(Value::String(text));
+    }
+    if let Some(pattern) = literal.strip_prefix('/').and_then(|l| l.strip_suffix('/')) {
+        return Some(Value::String(pattern.to_string()));
+    }
+    serde_json::from_str(literal).ok()
+}
+
+fn unquote(text: &str) -> Option<String> {
+    for quote in ['\'', '"'] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return Some(text[1..text.len() - 1].to_string());
+        }
+    }
+    None
+}
+
+/// Splits on a separator, ignoring separators inside quotes or brackets.
+fn split_outside_quotes<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut depth = 0i32;
+    let mut last = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let c = text[i..].chars().next().unwrap_or_default();
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[' | '(') => depth += 1,
+            (None, ']' | ')') => depth -= 1,
+            (None, _) if depth == 0 && text[i..].starts_with(separator) => {
+                parts.push(&text[last..i]);
+                i += separator.len();
+                last = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    parts.push(&text[last..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn resolve(doc: &Value, path: &str) -> Option<Value> {
+        ValuePath::parse(path).unwrap().resolve(doc)
+    }
+
+    fn doc() -> Value {
+        json!({
+            "user": { "name": "Ana", "address": { "city": "Lisbon" } },
+            "items": [
+                { "id": 1, "type": "book", "price": 8 },
+                { "id": 2, "type": "game", "price": 40 },
+                { "id": 3, "type": "book", "price": 15 }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_dotted_and_pointer_paths() {
+        let doc = doc();
+
+        assert_eq!(resolve(&doc, "user.address.city"), Some(json!("Lisbon")));
+        assert_eq!(resolve(&doc, "items[1].id"), Some(json!(2)));
+        assert_eq!(resolve(&doc, "items.0.id"), Some(json!(1)));
+        assert_eq!(resolve(&doc, "/user/address/city"), Some(json!("Lisbon")));
+        assert_eq!(resolve(&doc, "user.missing"), None);
+    }
+
+    #[test]
+    fn test_jsonpath_wildcards_and_slices() {
+        let doc = doc();
+
+        assert_eq!(resolve(&doc, "$.user.name"), Some(json!("Ana")));
+        assert_eq!(resolve(&doc, "$.items[*].id"), Some(json!([1, 2, 3])));
+        assert_eq!(resolve(&doc, "$.items[-1].id"), Some(json!(3)));
+        assert_eq!(resolve(&doc, "$.items[0:2].id"), Some(json!([1, 2])));
+        assert_eq!(resolve(&doc, "$['user']['name']"), Some(json!("Ana")));
+        assert_eq!(resolve(&doc, "$..city"), Some(json!(["Lisbon"])));
+        assert_eq!(resolve(&doc, "$.items[0,2].id"), Some(json!([1, 3])));
+    }
+
+    #[test]
+    fn test_jsonpath_filters() {
+        let doc = doc();
+
+        assert_eq!(
+            resolve(&doc, "$.items[?(@.type == 'book')].id"),
+            Some(json!([1, 3]))
+        );
+        assert_eq!(
+            resolve(&doc, "$.items[?(@.price > 10 && @.type != 'game')].id"),
+            Some(json!([3]))
+        );
+        assert_eq!(
+            resolve(&doc, "$.items[?(@.type =~ /^ga/ || @.id == 1)].id"),
+            Some(json!([1, 2]))
+        );
+        let missing = ValuePath::parse("$.items[?(@.discount)]").unwrap();
+        assert_eq!(missing.resolve_all(&doc).len(), 0);
+
+        assert!(ValuePath::parse("$.items[?(@.type =~ /^ga/)]").is_ok());
+        assert!(ValuePath::parse("$.items[?(@.type =~ /[a-/)]").is_err());
+    }
+}
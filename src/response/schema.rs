@@ -0,0 +1,623 @@
+use fake::{faker, Fake};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{json, Map, Value};
+
+use super::faker::{fake_datetime, generate_fake_value};
+use crate::request::FakeRng;
+
+/// Schemas nested deeper than this only get their required properties and
+/// minimum items, so recursive `$ref`s terminate.
+const MAX_DEPTH: usize = 6;
+
+/// Generates a value that validates against a JSON Schema. Internal `$ref`s
+/// (`#/definitions/...`, `#/$defs/...`) are resolved against the schema
+/// itself; strings are faked from their `format`, `pattern` or property name.
+pub fn generate_from_schema(schema: &Value, rng: &mut dyn FakeRng) -> Value {
+    SchemaFaker { root: schema }.generate(schema, None, 0, rng)
+}
+
+struct SchemaFaker<'a> {
+    root: &'a Value,
+}
+
+impl<'a> SchemaFaker<'a> {
+    fn generate(
+        &self,
+        schema: &Value,
+        name: Option<&str>,
+        depth: usize,
+        rng: &mut dyn FakeRng,
+    ) -> Value {
+        let schema = match schema {
+            Value::Object(schema) => schema,
+            Value::Bool(false) => return Value::Null,
+            _ => return json!(word(rng)),
+        };
+        if let Some(target) = schema.get("$ref").and_then(Value::as_str) {
+            return match self.resolve(target) {
+                Some(resolved) if depth < MAX_DEPTH * 2 => self.generate(resolved, name, depth + 1, rng),
+                _ => Value::Null,
+            };
+        }
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            return values
+                .choose(rng)
+                .cloned()
+                .unwrap_or(Value::Null);
+        }
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            let merged = self.merge(schema, all);
+            return self.generate_typed(&merged, name, depth, rng);
+        }
+        // Any branch of anyOf validates; the first of oneOf is the likeliest
+        // not to overlap the others
+        if let Some(branches) = schema.get("anyOf").and_then(Value::as_array) {
+            if let Some(branch) = branches.choose(rng) {
+                return self.generate(branch, name, depth + 1, rng);
+            }
+        }
+        if let Some(branch) = schema
+            .get("oneOf")
+            .and_then(Value::as_array)
+            .and_then(|b| b.first())
+        {
+            return self.generate(branch, name, depth + 1, rng);
+        }
+        self.generate_typed(schema, name, depth, rng)
+    }
+
+    fn generate_typed(
+        &self,
+        schema: &Map<String, Value>,
+        name: Option<&str>,
+        depth: usize,
+        rng: &mut dyn FakeRng,
+    ) -> Value {
+        match schema_type(schema) {
+            "null" => Value::Null,
+            "boolean" => Value::Bool(rng.gen_bool(0.5)),
+            "integer" => generate_integer(schema, rng),
+            "number" => generate_number(schema, rng),
+            "array" => self.generate_array(schema, name, depth, rng),
+            "object" => self.generate_object(schema, depth, rng),
+            _ => Value::String(generate_string(schema, name, rng)),
+        }
+    }
+
+    fn generate_object(
+        &self,
+        schema: &Map<String, Value>,
+        depth: usize,
+        rng: &mut dyn FakeRng,
+    ) -> Value {
+        let empty = Map::new();
+        let properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .unwrap_or(&empty);
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let min_properties = schema
+            .get("minProperties")
+            .and_then(Value::as_u64)
+            .map_or(0, |min| min as usize);
+        let max_properties = schema
+            .get("maxProperties")
+            .and_then(Value::as_u64)
+            .map_or(usize::MAX, |max| max as usize);
+        let additional = schema.get("additionalProperties").filter(|a| a.is_object());
+
+        let mut object = Map::new();
+        for name in &required {
+            let property = properties
+                .get(*name)
+                .or(additional)
+                .unwrap_or(&Value::Bool(true));
+            object.insert(
+                name.to_string(),
+                self.generate(property, Some(name), depth + 1, rng),
+            );
+        }
+        for (name, property) in properties {
+            let wanted = if depth < MAX_DEPTH { max_properties } else { min_properties };
+            if object.len() >= wanted {
+                break;
+            }
+            if !object.contains_key(name) {
+                object.insert(name.clone(), self.generate(property, Some(name), depth + 1, rng));
+            }
+        }
+        // Make up the rest of `minProperties` where extra properties are allowed
+        let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+        while !closed && object.len() < min_properties {
+            let name = format!("{}{}", word(rng), object.len());
+            let property = additional.unwrap_or(&Value::Bool(true));
+            let value = self.generate(property, None, depth + 1, rng);
+            object.insert(name, value);
+        }
+        Value::Object(object)
+    }
+
+    fn generate_array(
+        &self,
+        schema: &Map<String, Value>,
+        name: Option<&str>,
+        depth: usize,
+        rng: &mut dyn FakeRng,
+    ) -> Value {
+        // Items of `tags` are named `tag`
+        let item_name = name.map(|name| name.strip_suffix('s').unwrap_or(name));
+        if let Some(tuple) = schema.get("items").and_then(Value::as_array) {
+            return Value::Array(
+                tuple
+                    .iter()
+                    .map(|item| self.generate(item, item_name, depth + 1, rng))
+                    .collect(),
+            );
+        }
+
+        let default_min = if depth < MAX_DEPTH { 1 } else { 0 };
+        let min = schema
+            .get("minItems")
+            .and_then(Value::as_u64)
+            .map_or(default_min, |min| min as usize);
+        let max = schema
+            .get("maxItems")
+            .and_then(Value::as_u64)
+            .map_or(min.max(3), |max| max as usize);
+        let count = if depth < MAX_DEPTH && min < max {
+            rng.gen_range(min..=max)
+        } else {
+            min.min(max)
+        };
+
+        let item = schema.get("items").unwrap_or(&Value::Bool(true));
+        let unique = schema
+            .get("uniqueItems")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let mut items = Vec::with_capacity(count);
+        let mut attempts = 0;
+        while items.len() < count && attempts < count * 10 {
+            attempts += 1;
+            let value = self.generate(item, item_name, depth + 1, rng);
+            if !unique || !items.contains(&value) {
+                items.push(value);
+            }
+        }
+        Value::Array(items)
+    }
+
+    /// Folds `allOf` branches into the schema that holds them: properties
+    /// and required names are combined, other keywords taken from the last
+    /// branch that sets them.
+    fn merge(&self, schema: &Map<String, Value>, branches: &[Value]) -> Map<String, Value> {
+        let mut merged = schema.clone();
+        merged.remove("allOf");
+        for branch in branches {
+            let branch = match branch.get("$ref").and_then(Value::as_str) {
+                Some(target) => self.resolve(target).unwrap_or(branch),
+                None => branch,
+            };
+            let Some(branch) = branch.as_object() else {
+                continue;
+            };
+            for (key, value) in branch {
+                match (key.as_str(), merged.get_mut(key)) {
+                    ("properties", Some(Value::Object(properties))) => {
+                        if let Some(added) = value.as_object() {
+                            properties.extend(added.clone());
+                        }
+                    }
+                    ("required", Some(Value::Array(required))) => {
+                        if let Some(added) = value.as_array() {
+                            required.extend(added.iter().cloned());
+                        }
+                    }
+                    _ => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        merged
+    }
+
+    fn resolve(&self, target: &str) -> Option<&'a Value> {
+        match target.strip_prefix('#')? {
+            "" => Some(self.root),
+            pointer => self.root.pointer(pointer),
+        }
+    }
+}
+
+/// The declared type (the first non-null one of a list), or the type the
+/// schema's keywords imply.
+fn schema_type(schema: &Map<String, Value>) -> &str {
+    match schema.get("type") {
+        Some(Value::String(name)) => return name,
+        Some(Value::Array(names)) => {
+            let names: Vec<&str> = names.iter().filter_map(Value::as_str).collect();
+            if let Some(name) = names.iter().find(|name| **name != "null").or(names.first()) {
+                return name;
+            }
+        }
+        _ => {}
+    }
+    let has = |keys: &[&str]| keys.iter().any(|key| schema.contains_key(*key));
+    if has(&["properties", "required", "additionalProperties"]) {
+        "object"
+    } else if has(&["items", "minItems", "maxItems"]) {
+        "array"
+    } else if has(&[
+        "minimum",
+        "maximum",
+        "exclusiveMinimum",
+        "exclusiveMaximum",
+        "multipleOf",
+    ]) {
+        "number"
+    } else {
+        "string"
+    }
+}
+
+/// The inclusive bounds a number must fall in. `exclusiveMinimum` and
+/// `exclusiveMaximum` may be numbers (draft 6 on) or flags (draft 4).
+fn bounds(schema: &Map<String, Value>, step: f64) -> (Option<f64>, Option<f64>) {
+    let number = |key: &str| schema.get(key).and_then(Value::as_f64);
+    let flag = |key: &str| schema.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let mut min = number("minimum");
+    if flag("exclusiveMinimum") {
+        min = min.map(|min| min + step);
+    }
+    if let Some(exclusive) = number("exclusiveMinimum") {
+        min = Some(min.map_or(exclusive + step, |min| min.max(exclusive + step)));
+    }
+    let mut max = number("maximum");
+    if flag("exclusiveMaximum") {
+        max = max.map(|max| max - step);
+    }
+    if let Some(exclusive) = number("exclusiveMaximum") {
+        max = Some(max.map_or(exclusive - step, |max| max.min(exclusive - step)));
+    }
+    (min, max)
+}
+
+/// Without bounds a number falls in 0..=100; with one, within 100 of it.
+fn range(min: Option<f64>, max: Option<f64>) -> (f64, f64) {
+    match (min, max) {
+        (Some(min), Some(max)) => (min, max.max(min)),
+        (Some(min), None) => (min, min + 100.0),
+        (None, Some(max)) => (max.min(0.0), max),
+        (None, None) => (0.0, 100.0),
+    }
+}
+
+fn generate_integer(schema: &Map<String, Value>, rng: &mut dyn FakeRng) -> Value {
+    // Bounds beyond what f64 holds exactly are defaulted in i64
+    let (min, max) = bounds(schema, 1.0);
+    let (min, max) = match (min.map(|min| min.ceil() as i64), max.map(|max| max.floor() as i64)) {
+        (Some(min), Some(max)) => (min, max.max(min)),
+        (Some(min), None) => (min, min.saturating_add(100)),
+        (None, Some(max)) => (max.min(0), max),
+        (None, None) => (0, 100),
+    };
+    // Multiples from low * step to high * step all fall within the bounds;
+    // with none there, the bounds win
+    let multiples = schema
+        .get("multipleOf")
+        .and_then(Value::as_i64)
+        .filter(|m| *m > 0)
+        .map(|step| (div_ceil(min, step), max.div_euclid(step), step))
+        .filter(|(low, high, _)| low <= high);
+    match multiples {
+        Some((low, high, step)) => json!(rng.gen_range(low..=high) * step),
+        None => json!(rng.gen_range(min..=max)),
+    }
+}
+
+fn generate_number(schema: &Map<String, Value>, rng: &mut dyn FakeRng) -> Value {
+    let (min, max) = bounds(schema, 0.01);
+    let (min, max) = range(min, max);
+    let multiples = schema
+        .get("multipleOf")
+        .and_then(Value::as_f64)
+        .filter(|m| *m > 0.0)
+        .map(|step| ((min / step).ceil() as i64, (max / step).floor() as i64, step))
+        .filter(|(low, high, _)| low <= high);
+    if let Some((low, high, step)) = multiples {
+        return json!(rng.gen_range(low..=high) as f64 * step);
+    }
+    // Bounds too far apart to sample between, such as ±1e308, are narrowed
+    // around their midpoint
+    let (min, max) = if (max - min).is_finite() {
+        (min, max)
+    } else {
+        let mid = min / 2.0 + max / 2.0;
+        ((mid - 1e6).max(min), (mid + 1e6).min(max))
+    };
+    let value = rng.gen_range(min..=max);
+    let rounded = (value * 100.0).round() / 100.0;
+    json!(if (min..=max).contains(&rounded) {
+        rounded
+    } else {
+        value
+    })
+}
+
+fn div_ceil(value: i64, step: i64) -> i64 {
+    value.div_euclid(step) + i64::from(value.rem_euclid(step) != 0)
+}
+
+/// A string from the schema's `format`, else its `pattern`, else a fake
+/// chosen by the property name, padded or cut to the length bounds. The
+/// length bounds win over a format or pattern that cannot meet them.
+fn generate_string(
+    schema: &Map<String, Value>,
+    name: Option<&str>,
+    rng: &mut dyn FakeRng,
+) -> String {
+    let min = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let max = schema
+        .get("maxLength")
+        .and_then(Value::as_u64)
+        .map(|max| max as usize);
+
+    let fits = |text: &str| {
+        let length = text.chars().count();
+        length >= min && max.is_none_or(|max| length <= max)
+    };
+
+    if let Some(format) = schema.get("format").and_then(Value::as_str) {
+        for _ in 0..20 {
+            match generate_format(format, rng) {
+                Some(text) if fits(&text) => return text,
+                Some(_) => {}
+                None => break,
+            }
+        }
+    }
+    if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+        // Out of bounds after every try, the length bounds win
+        if let Some(text) = generate_pattern(pattern, min, max, rng) {
+            return fit_length(text, min, max, rng);
+        }
+    }
+    let text = match name.and_then(fake_type_for) {
+        Some(fake_type) => match generate_fake_value(fake_type, &Map::new(), rng) {
+            Value::String(text) => text,
+            other => other.to_string(),
+        },
+        None => word(rng),
+    };
+    fit_length(text, min, max, rng)
+}
+
+fn generate_format(format: &str, rng: &mut dyn FakeRng) -> Option<String> {
+    let fake = |fake_type: &str, rng: &mut dyn FakeRng| {
+        generate_fake_value(fake_type, &Map::new(), rng)
+            .as_str()
+            .map(String::from)
+    };
+    match format {
+        "email" | "idn-email" => fake("email", rng),
+        "uuid" => fake("uuid", rng),
+        "uri" | "url" | "iri" => fake("url", rng),
+        "date-time" => {
+            Some(fake_datetime(rng).to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        }
+        "date" => Some(fake_datetime(rng).format("%Y-%m-%d").to_string()),
+        "time" => Some(fake_datetime(rng).format("%H:%M:%SZ").to_string()),
+        "hostname" | "idn-hostname" => {
+            let domain: String = faker::internet::en::DomainSuffix().fake_with_rng(rng);
+            Some(format!("{}.{}", word(rng), domain))
+        }
+        "ipv4" => {
+            // Leading zero octets (`0.x.x.x`) are rejected by some validators
+            let octets: [u8; 3] = rng.gen();
+            let first: u8 = rng.gen_range(1..=223);
+            Some(format!(
+                "{}.{}.{}.{}",
+                first, octets[0], octets[1], octets[2]
+            ))
+        }
+        "ipv6" => {
+            let ip: std::net::Ipv6Addr = faker::internet::en::IPv6().fake_with_rng(rng);
+            Some(ip.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// A string matching the pattern, within the length bounds if one of a
+/// few tries lands there, otherwise the last try. None when the pattern
+/// cannot be generated from.
+fn generate_pattern(
+    pattern: &str,
+    min: usize,
+    max: Option<usize>,
+    rng: &mut dyn FakeRng,
+) -> Option<String> {
+    let pattern = pattern.trim_start_matches('^').trim_end_matches('$');
+    let generator = rand_regex::Regex::compile(pattern, 10).ok()?;
+    let mut text: String = rng.sample::<String, _>(&generator);
+    for _ in 0..20 {
+        let length = text.chars().count();
+        if length >= min && max.is_none_or(|max| length <= max) {
+            break;
+        }
+        text = rng.sample::<String, _>(&generator);
+    }
+    Some(text)
+}
+
+fn fit_length(
+    mut text: String,
+    min: usize,
+    max: Option<usize>,
+    rng: &mut dyn FakeRng,
+) -> String {
+    while text.chars().count() < min {
+        text.push(' ');
+        text.push_str(&word(rng));
+    }
+    match max {
+        Some(max) if text.chars().count() > max => text.chars().take(max).collect(),
+        _ => text,
+    }
+}
+
+/// The fake type a property name suggests, such as `email` for
+/// `contactEmail` or `zipcode` for `postal_code`.
+fn fake_type_for(name: &str) -> Option<&'static str> {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    let fake_type = match name.as_str() {
+        "id" | "uuid" | "guid" => "uuid",
+        "name" | "fullname" | "displayname" => "name",
+        "firstname" | "givenname" => "firstname",
+        "lastname" | "surname" | "familyname" => "lastname",
+        "username" | "login" | "handle" => "username",
+        "phone" | "phonenumber" | "mobile" | "telephone" => "phone",
+        "address" | "streetaddress" => "address",
+        "city" | "town" => "city",
+        "country" => "country",
+        "zip" | "zipcode" | "postalcode" | "postcode" => "zipcode",
+        "company" | "companyname" | "organization" | "employer" => "company",
+        "jobtitle" | "occupation" => "jobtitle",
+        "description" | "bio" | "summary" | "comment" => "sentence",
+        "ip" | "ipaddress" => "ipv4",
+        "color" | "colour" => "color",
+        "createdat" | "updatedat" | "timestamp" | "datetime" => "datetime",
+        _ if name.ends_with("email") => "email",
+        _ if name.ends_with("url") || name == "website" || name == "homepage" => "url",
+        _ if name.ends_with("phone") => "phone",
+        _ => return None,
+    };
+    Some(fake_type)
+}
+
+fn word(rng: &mut dyn FakeRng) -> String {
+    faker::lorem::en::Word().fake_with_rng(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonschema::JSONSchema;
+
+    fn assert_conforms(schema: Value) -> Value {
+        let compiled = JSONSchema::compile(&schema).unwrap();
+        let mut last = Value::Null;
+        for _ in 0..50 {
+            let value = generate_from_schema(&schema, &mut rand::thread_rng());
+            if let Err(errors) = compiled.validate(&value) {
+                let errors: Vec<String> = errors.map(|e| e.to_string()).collect();
+                panic!("{} does not conform: {:?}", value, errors);
+            }
+            last = value;
+        }
+        last
+    }
+
+    #[test]
+    fn test_generated_objects_validate_against_their_schema() {
+        let user = assert_conforms(json!({
+            "type": "object",
+            "required": ["id", "email", "age", "tags", "status"],
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "email": { "type": "string", "format": "email" },
+                "contactEmail": { "type": "string" },
+                "website": { "type": "string", "format": "uri" },
+                "createdAt": { "type": "string", "format": "date-time" },
+                "birthday": { "type": "string", "format": "date" },
+                "age": { "type": "integer", "minimum": 18, "maximum": 99 },
+                "score": { "type": "number", "exclusiveMinimum": 0, "maximum": 1 },
+                "lots": { "type": "integer", "multipleOf": 5, "minimum": 1, "maximum": 50 },
+                "sku": { "type": "string", "pattern": "^[A-Z]{3}-[0-9]{4}$" },
+                "nickname": { "type": "string", "minLength": 12, "maxLength": 20 },
+                "status": { "enum": ["active", "banned"] },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": "string", "maxLength": 8 },
+                    "minItems": 2,
+                    "maxItems": 4,
+                    "uniqueItems": true
+                },
+                "manager": { "$ref": "#/definitions/person" },
+                "deleted": { "type": ["null", "boolean"] }
+            },
+            "definitions": {
+                "person": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": { "name": { "type": "string" }, "city": { "type": "string" } }
+                }
+            }
+        }));
+        assert!(user["contactEmail"].as_str().unwrap().contains('@'));
+        assert!(user["deleted"].is_boolean());
+    }
+
+    #[test]
+    fn test_composed_and_recursive_schemas_terminate() {
+        assert_conforms(json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "required": ["value", "children"],
+                    "properties": {
+                        "value": { "type": "integer" },
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/node" } }
+                    }
+                }
+            },
+            "allOf": [
+                { "$ref": "#/$defs/node" },
+                { "properties": { "label": { "const": "root" } }, "required": ["label"] }
+            ]
+        }));
+        assert_conforms(json!({
+            "anyOf": [{ "type": "string", "format": "ipv4" }, { "type": "integer", "maximum": -5 }]
+        }));
+    }
+
+    #[test]
+    fn test_hard_constraints_stay_within_bounds() {
+        assert_conforms(json!({ "type": "number", "minimum": -1e308, "maximum": 1e308 }));
+        assert_conforms(json!({ "type": "string", "pattern": "^[a-z]+$", "maxLength": 3 }));
+        assert_conforms(json!({ "type": "string", "format": "email", "maxLength": 24 }));
+        assert_conforms(json!({
+            "type": "object",
+            "minProperties": 3,
+            "properties": { "a": { "type": "integer" } },
+            "additionalProperties": { "type": "boolean" }
+        }));
+
+        // Checked here rather than by the validator, which compares large
+        // integers as floats
+        let integer = |schema: Value| {
+            generate_from_schema(&schema, &mut rand::thread_rng())
+                .as_i64()
+                .unwrap()
+        };
+        assert_eq!(integer(json!({ "type": "integer", "minimum": i64::MIN, "multipleOf": 7 })) % 7, 0);
+        assert!(integer(json!({ "type": "integer", "minimum": i64::MAX - 10, "multipleOf": 3 })) >= i64::MAX - 10);
+
+        // Without a multiple between the bounds, the bounds are kept
+        let value = integer(json!({ "type": "integer", "minimum": 1, "maximum": 4, "multipleOf": 5 }));
+        assert!((1..=4).contains(&value));
+    }
+}
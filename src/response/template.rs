@@ -0,0 +1,450 @@
+use base64::Engine;
+use chrono::format::{Item, StrftimeItems};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::faker::generate_fake_value;
+use super::path::{value_to_string, ValuePath};
+use crate::request::RequestContext;
+
+/// A string with `{{ ... }}` placeholders, parsed once when the config is
+/// loaded and rendered per request.
+///
+/// A placeholder holds an expression followed by optional helpers:
+/// `{{ query.name | default 'guest' | upper }}`.
+#[derive(Clone)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Clone)]
+enum Part {
+    Text(String),
+    Placeholder(Expression, Vec<Helper>),
+}
+
+#[derive(Clone)]
+enum Expression {
+    Param(String),
+    Query(String),
+    Header(String),
+    Cookie(String),
+    Body(ValuePath),
+    Fake(String),
+    Now,
+    Method,
+    Path,
+    Literal(String),
+}
+
+#[derive(Clone)]
+enum Helper {
+    Upper,
+    Lower,
+    Default(String),
+    Json,
+    Base64,
+    Date(String),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| format!("unclosed placeholder in \"{}\"", source))?;
+            let (expression, helpers) = parse_placeholder(after[..end].trim())
+                .map_err(|e| format!("{} in \"{}\"", e, source))?;
+            parts.push(Part::Placeholder(expression, helpers));
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            parts,
+        })
+    }
+
+    /// Turns `text` into a template source that renders as `text` itself,
+    /// by writing each `{{` as a quoted literal.
+    pub fn escape(text: &str) -> String {
+        text.replace("{{", "{{ '{{' }}")
+    }
+
+    /// Whether the string contains any placeholder at all.
+    pub fn is_template(source: &str) -> bool {
+        source.contains("{{")
+    }
+
+    pub fn render(&self, ctx: &RequestContext) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(expression, helpers) => {
+                    value_to_string(&evaluate(expression, helpers, ctx))
+                }
+            })
+            .collect()
+    }
+
+    /// Renders the template as a JSON value. A string that is nothing but a
+    /// single placeholder keeps the type of what it refers to, so
+    /// `"{{body.items}}"` yields the array rather than its text.
+    pub fn render_value(&self, ctx: &RequestContext) -> Value {
+        match self.parts.as_slice() {
+            [Part::Placeholder(expression, helpers)] => evaluate(expression, helpers, ctx),
+            _ => Value::String(self.render(ctx)),
+        }
+    }
+}
+
+impl fmt::Debug for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Template({:?})", self.source)
+    }
+}
+
+impl Serialize for Template {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Template {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Template::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A JSON response template together with every interpolated string and
+/// `$body` path inside it, compiled when the config is loaded.
+#[derive(Clone, Default)]
+pub struct BodyTemplate {
+    value: Value,
+    templates: Arc<HashMap<String, Template>>,
+    paths: Arc<HashMap<String, ValuePath>>,
+}
+
+impl BodyTemplate {
+    pub fn new(value: Value) -> Result<Self, String> {
+        let mut templates = HashMap::new();
+        let mut paths = HashMap::new();
+        collect_templates(&value, &mut templates, &mut paths)?;
+        Ok(Self {
+            value,
+            templates: Arc::new(templates),
+            paths: Arc::new(paths),
+        })
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The compiled template for a string found in the body, if it has
+    /// placeholders.
+    pub fn template(&self, source: &str) -> Option<&Template> {
+        self.templates.get(source)
+    }
+
+    /// The parsed path of a `$body` directive found in the body.
+    pub fn path(&self, source: &str) -> Option<&ValuePath> {
+        self.paths.get(source)
+    }
+}
+
+impl From<Value> for BodyTemplate {
+    /// Wraps a value built in code. Strings with malformed placeholders are
+    /// left as plain text, and malformed `$body` paths resolve to null.
+    fn from(value: Value) -> Self {
+        let mut templates = HashMap::new();
+        let mut paths = HashMap::new();
+        let _ = collect_templates(&value, &mut templates, &mut paths);
+        Self {
+            value,
+            templates: Arc::new(templates),
+            paths: Arc::new(paths),
+        }
+    }
+}
+
+impl fmt::Debug for BodyTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl Serialize for BodyTemplate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BodyTemplate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        BodyTemplate::new(value).map_err(serde::de::Error::custom)
+    }
+}
+
+fn collect_templates(
+    value: &Value,
+    templates: &mut HashMap<String, Template>,
+    paths: &mut HashMap<String, ValuePath>,
+) -> Result<(), String> {
+    match value {
+        Value::String(s) if Template::is_template(s) && !templates.contains_key(s) => {
+            templates.insert(s.clone(), Template::parse(s)?);
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_templates(item, templates, paths)?;
+            }
+        }
+        Value::Object(map) => {
+            if let Some(path) = map.get("$body").and_then(Value::as_str) {
+                if !paths.contains_key(path) {
+                    paths.insert(path.to_string(), ValuePath::parse(path)?);
+                }
+            }
+            for item in map.values() {
+                collect_templates(item, templates, paths)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse_placeholder(content: &str) -> Result<(Expression, Vec<Helper>), String> {
+    let mut sections = split_pipes(content).into_iter();
+    let expression = parse_expression(sections.next().unwrap_or_default().trim())?;
+    let helpers = sections
+        .map(|section| parse_helper(section.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((expression, helpers))
+}
+
+fn parse_expression(expr: &str) -> Result<Expression, String> {
+    if let Some(literal) = unquote(expr) {
+        return Ok(Expression::Literal(literal));
+    }
+    match expr {
+        "now" => return Ok(Expression::Now),
+        "method" => return Ok(Expression::Method),
+        "path" => return Ok(Expression::Path),
+        "body" => return ValuePath::parse("").map(Expression::Body),
+        _ => {}
+    }
+
+    let (root, key) = expr
+        .split_once('.')
+        .ok_or_else(|| format!("unknown expression \"{}\"", expr))?;
+    if key.is_empty() {
+        return Err(format!("missing name after \"{}.\"", root));
+    }
+    let key = key.to_string();
+    match root {
+        "param" | "params" => Ok(Expression::Param(key)),
+        "query" => Ok(Expression::Query(key)),
+        "header" | "headers" => Ok(Expression::Header(key)),
+        "cookie" | "cookies" => Ok(Expression::Cookie(key)),
+        "body" => ValuePath::parse(&key).map(Expression::Body),
+        "fake" => Ok(Expression::Fake(key)),
+        _ => Err(format!("unknown expression \"{}\"", expr)),
+    }
+}
+
+fn parse_helper(section: &str) -> Result<Helper, String> {
+    let (name, arg) = match section.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim())),
+        None => (section, None),
+    };
+    let arg = arg.map(|a| unquote(a).unwrap_or_else(|| a.to_string()));
+
+    match (name, arg) {
+        ("upper", None) => Ok(Helper::Upper),
+        ("lower", None) => Ok(Helper::Lower),
+        ("json", None) => Ok(Helper::Json),
+        ("base64", None) => Ok(Helper::Base64),
+        ("default", Some(fallback)) => Ok(Helper::Default(fallback)),
+        ("date", Some(format)) => {
+            if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+                return Err(format!("invalid date format \"{}\"", format));
+            }
+            Ok(Helper::Date(format))
+        }
+        ("date", None) => Ok(Helper::Date("%Y-%m-%dT%H:%M:%S%.3fZ".to_string())),
+        ("default", None) => Err("helper \"default\" needs a value".to_string()),
+        (name, _) => Err(format!("unknown helper \"{}\"", name)),
+    }
+}
+
+fn evaluate(expression: &Expression, helpers: &[Helper], ctx: &RequestContext) -> Value {
+    let mut value = match expression {
+        Expression::Param(name) => ctx
+            .params
+            .get(name)
+            .map(|v| Value::String(v.clone()))
+            .unwrap_or(Value::Null),
+        Expression::Query(name) => match ctx.query_values(name) {
+            [] => Value::Null,
+            [single] => Value::String(single.clone()),
+            many => Value::from(many.to_vec()),
+        },
+        Expression::Header(name) => ctx
+            .headers
+            .get(name.as_str())
+            .and_then(|v| v.to_str().ok())
+            .map(|v| Value::String(v.to_string()))
+            .unwrap_or(Value::Null),
+        Expression::Cookie(name) => ctx
+            .cookies
+            .get(name)
+            .map(|v| Value::String(v.clone()))
+            .unwrap_or(Value::Null),
+        Expression::Body(path) => ctx
+            .body
+            .as_ref()
+            .and_then(|body| path.resolve(body))
+            .unwrap_or(Value::Null),
+        Expression::Fake(fake_type) => {
+            ctx.rng.with(|rng| generate_fake_value(fake_type, &Map::new(), rng))
+        }
+        Expression::Now => Value::String(chrono::Utc::now().to_rfc3339()),
+        Expression::Method => Value::String(ctx.method.clone()),
+        Expression::Path => Value::String(ctx.path.clone()),
+        Expression::Literal(text) => Value::String(text.clone()),
+    };
+
+    for helper in helpers {
+        value = apply_helper(helper, value);
+    }
+    value
+}
+
+fn apply_helper(helper: &Helper, value: Value) -> Value {
+    match helper {
+        Helper::Default(fallback) => match value {
+            Value::Null => Value::String(fallback.clone()),
+            Value::String(s) if s.is_empty() => Value::String(fallback.clone()),
+            other => other,
+        },
+        Helper::Upper => Value::String(value_to_string(&value).to_uppercase()),
+        Helper::Lower => Value::String(value_to_string(&value).to_lowercase()),
+        Helper::Json => Value::String(value.to_string()),
+        Helper::Base64 => Value::String(
+            base64::engine::general_purpose::STANDARD.encode(value_to_string(&value)),
+        ),
+        Helper::Date(format) => {
+            let text = value_to_string(&value);
+            match chrono::DateTime::parse_from_rfc3339(&text) {
+                Ok(date) => Value::String(date.format(format).to_string()),
+                Err(_) => value,
+            }
+        }
+    }
+}
+
+/// Splits a placeholder on `|`, ignoring pipes inside quoted arguments.
+fn split_pipes(content: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut last = 0;
+    for (i, c) in content.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '|') => {
+                sections.push(&content[last..i]);
+                last = i + 1;
+            }
+            _ => {}
+        }
+    }
+    sections.push(&content[last..]);
+    sections
+}
+
+fn unquote(text: &str) -> Option<String> {
+    for quote in ['\'', '"'] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return Some(text[1..text.len() - 1].to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, Method, Uri};
+    use serde_json::json;
+
+    fn request() -> RequestContext {
+        let uri: Uri = "/users/42?name=ana&tag=a&tag=b".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req-1".parse().unwrap());
+        let mut ctx = RequestContext::new(
+            &Method::POST,
+            &uri,
+            headers,
+            Some(json!({ "user": { "city": "Porto" }, "items": [1, 2] })),
+        );
+        ctx.params.insert("id".to_string(), "42".to_string());
+        ctx
+    }
+
+    #[test]
+    fn test_interpolates_request_values() {
+        let ctx = request();
+        let template =
+            Template::parse("Hello {{query.name | upper}} #{{param.id}} from {{body.user.city}}")
+                .unwrap();
+        assert_eq!(template.render(&ctx), "Hello ANA #42 from Porto");
+
+        let template = Template::parse("{{ header.X-Request-Id }}/{{method | lower}}").unwrap();
+        assert_eq!(template.render(&ctx), "req-1/post");
+    }
+
+    #[test]
+    fn test_helpers() {
+        let ctx = request();
+        let render = |source: &str| Template::parse(source).unwrap().render_value(&ctx);
+
+        assert_eq!(render("{{query.missing | default 'guest'}}"), "guest");
+        assert_eq!(render("{{body.user | json}}"), r#"{"city":"Porto"}"#);
+        assert_eq!(render("{{param.id | base64}}"), "NDI=");
+        assert_eq!(render("{{'2024-05-06T07:08:09Z' | date '%d/%m/%Y'}}"), "06/05/2024");
+        assert_eq!(render("{{body.items}}"), json!([1, 2]));
+        assert_eq!(render("{{query.tag}}"), json!(["a", "b"]));
+        assert!(render("/users/{{fake.uuid}}").as_str().unwrap().len() > "/users/".len());
+    }
+
+    #[test]
+    fn test_invalid_templates_fail_to_parse() {
+        assert!(Template::parse("{{query.name").is_err());
+        assert!(Template::parse("{{unknown.name}}").is_err());
+        assert!(Template::parse("{{param.id | shout}}").is_err());
+        assert!(Template::parse("{{now | date '%Q'}}").is_err());
+        assert!(Template::parse("{{body.$.items[?(@.sku =~ /[a-/)]}}").is_err());
+        assert!(serde_json::from_value::<BodyTemplate>(json!({ "a": ["{{nope}}"] })).is_err());
+    }
+}
@@ -0,0 +1,423 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::config::{validate_config, ConditionCheck, ConfigManager, MockConfig};
+use crate::openapi::export_openapi;
+use crate::request::RequestContext;
+use crate::state::{AppState, JournalEntry};
+
+use super::{check_condition, path_matches};
+
+/// Serves the built-in admin endpoints, or returns `None` when `path` is not
+/// one of them.
+pub fn handle_admin_request(
+    state: &AppState,
+    ctx: &RequestContext,
+) -> Option<(StatusCode, HeaderMap, Json<Value>)> {
+    let (method, path) = (ctx.method.as_str(), ctx.path.as_str());
+    // Admin bodies are JSON whatever Content-Type the client sent
+    let json_body: Option<Value> = serde_json::from_slice(&ctx.raw_body).ok();
+    let body = json_body.as_ref().or(ctx.body.as_ref());
+    if path == "/_config" {
+        return Some(handle_config_endpoint(&state.config, method, body));
+    }
+    if path == "/_scenarios" || path.starts_with("/_scenarios/") {
+        let rest = path["/_scenarios".len()..].trim_start_matches('/');
+        return Some(handle_scenarios_endpoint(state, method, rest, body));
+    }
+    if path == "/_sequences" || path == "/_sequences/reset" {
+        return Some(handle_sequences_endpoint(state, method, path));
+    }
+    if path == "/_requests" {
+        return Some(handle_requests_endpoint(state, ctx));
+    }
+    if path == "/_verify" {
+        return Some(handle_verify_endpoint(state, method, body));
+    }
+    if path == "/_openapi.json" {
+        if method != "GET" {
+            return Some(error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "Use GET to export the OpenAPI document".to_string(),
+            ));
+        }
+        return Some(ok(export_openapi(&state.config.get_config())));
+    }
+    None
+}
+
+fn handle_config_endpoint(
+    config_manager: &Arc<ConfigManager>,
+    method: &str,
+    body: Option<&Value>,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    match method {
+        "GET" => {
+            // Return current configuration
+            let config = config_manager.get_config();
+            let config_json = serde_json::to_value(&config).unwrap_or(json!({}));
+            (
+                StatusCode::OK,
+                HeaderMap::new(),
+                Json(json!({
+                    "success": true,
+                    "config": config_json
+                })),
+            )
+        }
+        "POST" | "PUT" => {
+            // Update configuration from request body
+            match body {
+                Some(config_value) => {
+                    match serde_json::from_value::<MockConfig>(config_value.clone())
+                        .map_err(|e| e.to_string())
+                        .and_then(|config| validate_config(&config).map(|_| config))
+                    {
+                        Ok(new_config) => {
+                            config_manager.update_config(new_config);
+                            tracing::info!("Configuration updated via HTTP");
+                            (
+                                StatusCode::OK,
+                                HeaderMap::new(),
+                                Json(json!({
+                                    "success": true,
+                                    "message": "Configuration updated successfully",
+                                    "endpoints_count": config_manager.get_config().endpoints.len()
+                                })),
+                            )
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to parse config: {}", e);
+                            (
+                                StatusCode::BAD_REQUEST,
+                                HeaderMap::new(),
+                                Json(json!({
+                                    "success": false,
+                                    "error": format!("Invalid configuration: {}", e)
+                                })),
+                            )
+                        }
+                    }
+                }
+                None => (
+                    StatusCode::BAD_REQUEST,
+                    HeaderMap::new(),
+                    Json(json!({
+                        "success": false,
+                        "error": "Request body is required"
+                    })),
+                ),
+            }
+        }
+        _ => (
+            StatusCode::METHOD_NOT_ALLOWED,
+            HeaderMap::new(),
+            Json(json!({
+                "success": false,
+                "error": "Method not allowed. Use GET to view config or POST/PUT to update."
+            })),
+        ),
+    }
+}
+
+
+/// `GET /_scenarios` lists every scenario's state, `POST /_scenarios/reset`
+/// resets them all, and `GET`, `PUT {"state": ...}` or `POST .../reset` on
+/// `/_scenarios/:name` work on a single one.
+fn handle_scenarios_endpoint(
+    state: &AppState,
+    method: &str,
+    rest: &str,
+    body: Option<&Value>,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    let config = state.config.get_config();
+    let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", []) => ok(json!({
+            "success": true,
+            "scenarios": state.scenarios.snapshot(&config.scenarios)
+        })),
+        ("POST", ["reset"]) => {
+            state.scenarios.reset_all();
+            tracing::info!("All scenarios reset");
+            ok(json!({
+                "success": true,
+                "scenarios": state.scenarios.snapshot(&config.scenarios)
+            }))
+        }
+        (_, [name, ..]) if !config.scenarios.contains_key(*name) => error(
+            StatusCode::NOT_FOUND,
+            format!("Unknown scenario: {}", name),
+        ),
+        ("GET", [name]) => {
+            let scenario = &config.scenarios[*name];
+            ok(json!({
+                "success": true,
+                "name": name,
+                "state": state.scenarios.state(name, scenario),
+                "initial": scenario.initial,
+                "states": scenario.states
+            }))
+        }
+        ("POST", [name, "reset"]) => {
+            state.scenarios.reset(name);
+            tracing::info!("Scenario {} reset", name);
+            ok(json!({
+                "success": true,
+                "name": name,
+                "state": config.scenarios[*name].initial
+            }))
+        }
+        ("PUT", [name]) => {
+            let scenario = &config.scenarios[*name];
+            let Some(new_state) = body.and_then(|b| b.get("state")).and_then(Value::as_str) else {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    "Request body must be {\"state\": \"...\"}".to_string(),
+                );
+            };
+            if !scenario.states.is_empty() && !scenario.states.iter().any(|s| s == new_state) {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    format!("Scenario {} has no state \"{}\"", name, new_state),
+                );
+            }
+            state.scenarios.set(name, new_state);
+            tracing::info!("Scenario {} set to \"{}\"", name, new_state);
+            ok(json!({
+                "success": true,
+                "name": name,
+                "state": new_state
+            }))
+        }
+        _ => error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Method not allowed on this scenario path".to_string(),
+        ),
+    }
+}
+
+/// `GET /_sequences` shows how often each response sequence was served and
+/// `POST /_sequences/reset` starts them all over.
+fn handle_sequences_endpoint(
+    state: &AppState,
+    method: &str,
+    path: &str,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    match (method, path) {
+        ("GET", "/_sequences") => ok(json!({
+            "success": true,
+            "sequences": state.sequences.snapshot()
+        })),
+        ("POST", "/_sequences/reset") => {
+            state.sequences.reset();
+            tracing::info!("Response sequences reset");
+            ok(json!({
+                "success": true,
+                "message": "Response sequences reset"
+            }))
+        }
+        _ => error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Use GET /_sequences or POST /_sequences/reset".to_string(),
+        ),
+    }
+}
+
+/// `GET /_requests` lists journaled requests, optionally filtered by
+/// `method`, `path` (a route such as `/users/:id`), `matched=true|false` and
+/// `limit` (the most recent N). `DELETE /_requests` clears the journal.
+fn handle_requests_endpoint(
+    state: &AppState,
+    ctx: &RequestContext,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    match ctx.method.as_str() {
+        "GET" => {
+            let filter = |key: &str| ctx.query_values(key).first().map(String::as_str);
+            let mut entries: Vec<JournalEntry> = state
+                .journal
+                .entries()
+                .into_iter()
+                .filter(|entry| {
+                    filter("method").is_none_or(|m| m.eq_ignore_ascii_case(&entry.request.method))
+                        && filter("path").is_none_or(|p| path_matches(p, &entry.request.path))
+                        && filter("matched").is_none_or(|m| (m == "true") == entry.matched())
+                })
+                .collect();
+            if let Some(limit) = filter("limit").and_then(|l| l.parse::<usize>().ok()) {
+                entries.drain(..entries.len().saturating_sub(limit));
+            }
+
+            let requests: Vec<Value> = entries.iter().map(JournalEntry::to_json).collect();
+            ok(json!({
+                "success": true,
+                "total": requests.len(),
+                "requests": requests
+            }))
+        }
+        "DELETE" => {
+            state.journal.clear();
+            tracing::info!("Request journal cleared");
+            ok(json!({
+                "success": true,
+                "message": "Request journal cleared"
+            }))
+        }
+        _ => error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Use GET to list requests or DELETE to clear them".to_string(),
+        ),
+    }
+}
+
+/// Body of `POST /_verify`: a condition the journaled requests are matched
+/// against (every request when omitted) and how many must match.
+#[derive(Debug, serde::Deserialize)]
+struct Verification {
+    #[serde(default)]
+    request: Option<ConditionCheck>,
+    #[serde(default)]
+    exactly: Option<usize>,
+    #[serde(default)]
+    at_least: Option<usize>,
+    #[serde(default)]
+    at_most: Option<usize>,
+}
+
+/// Checks how many journaled requests match a pattern. Answers 200 when the
+/// count is as expected and 417 otherwise.
+fn handle_verify_endpoint(
+    state: &AppState,
+    method: &str,
+    body: Option<&Value>,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    if method != "POST" {
+        return error(StatusCode::METHOD_NOT_ALLOWED, "Use POST to verify".to_string());
+    }
+    let verification = match body.map(|b| serde_json::from_value::<Verification>(b.clone())) {
+        Some(Ok(verification)) => verification,
+        Some(Err(e)) => return error(StatusCode::BAD_REQUEST, format!("Invalid verification: {}", e)),
+        None => return error(StatusCode::BAD_REQUEST, "Request body is required".to_string()),
+    };
+    if let Some(Err(e)) = verification.request.as_ref().map(ConditionCheck::validate) {
+        return error(StatusCode::BAD_REQUEST, format!("Invalid verification: {}", e));
+    }
+
+    let count = state
+        .journal
+        .entries()
+        .iter()
+        .filter(|entry| {
+            verification
+                .request
+                .as_ref()
+                .is_none_or(|condition| check_condition(condition, &entry.request))
+        })
+        .count();
+
+    // Without any bound, at least one matching request is expected
+    let at_least = match (verification.exactly, verification.at_least, verification.at_most) {
+        (None, None, None) => Some(1),
+        (_, at_least, _) => at_least,
+    };
+    let verified = verification.exactly.is_none_or(|n| count == n)
+        && at_least.is_none_or(|n| count >= n)
+        && verification.at_most.is_none_or(|n| count <= n);
+
+    let status = if verified {
+        StatusCode::OK
+    } else {
+        StatusCode::EXPECTATION_FAILED
+    };
+    (
+        status,
+        HeaderMap::new(),
+        Json(json!({
+            "success": verified,
+            "count": count,
+            "expected": {
+                "exactly": verification.exactly,
+                "at_least": at_least,
+                "at_most": verification.at_most
+            }
+        })),
+    )
+}
+
+fn ok(body: Value) -> (StatusCode, HeaderMap, Json<Value>) {
+    (StatusCode::OK, HeaderMap::new(), Json(body))
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, HeaderMap, Json<Value>) {
+    (
+        status,
+        HeaderMap::new(),
+        Json(json!({
+            "success": false,
+            "error": message
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn state() -> (tempfile::TempDir, AppState) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("endpoints.yaml");
+        std::fs::write(&path, "endpoints: []\n").unwrap();
+        let config = ConfigManager::new(path.to_str().unwrap()).await.unwrap();
+        (dir, AppState::new(Arc::new(config)))
+    }
+
+    fn request(method: &str, path: &str, body: Option<Value>) -> RequestContext {
+        RequestContext {
+            method: method.to_string(),
+            path: path.to_string(),
+            body,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_counts_journaled_requests() {
+        let (_dir, state) = state().await;
+        for name in ["Ana", "Bruno"] {
+            let created = request("POST", "/users", Some(json!({ "name": name })));
+            state.journal.record(&created, Some("#1 POST /users".to_string()), 10);
+        }
+        state.journal.record(&request("GET", "/missing", None), None, 10);
+
+        let verify = |body: Value| {
+            let ctx = request("POST", "/_verify", Some(body));
+            handle_admin_request(&state, &ctx).unwrap()
+        };
+
+        let (status, _, Json(body)) = verify(json!({
+            "request": { "method": "POST", "path": "/users" },
+            "exactly": 2
+        }));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 2);
+
+        let (status, _, _) = verify(json!({
+            "request": { "body_field": "name", "equals": "Carla" }
+        }));
+        assert_eq!(status, StatusCode::EXPECTATION_FAILED);
+
+        let (status, _, _) = verify(json!({ "at_most": 2 }));
+        assert_eq!(status, StatusCode::EXPECTATION_FAILED);
+
+        let mut unmatched = request("GET", "/_requests", None);
+        unmatched.query = crate::request::parse_query("matched=false");
+        let (_, _, Json(body)) = handle_admin_request(&state, &unmatched).unwrap();
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["requests"][0]["path"], "/missing");
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use crate::config::{Comparison, ConditionCheck, Predicate, QuerySubject, ValueMatch};
+use crate::request::RequestContext;
+use crate::response::value_to_string;
+
+/// Evaluates a condition tree against a request.
+pub fn check_condition(condition: &ConditionCheck, ctx: &RequestContext) -> bool {
+    match condition {
+        ConditionCheck::All { all } => all.iter().all(|c| check_condition(c, ctx)),
+        ConditionCheck::Any { any } => any.iter().any(|c| check_condition(c, ctx)),
+        ConditionCheck::Not { not } => !check_condition(not, ctx),
+        ConditionCheck::Predicate(predicate) => check_predicate(predicate, ctx),
+    }
+}
+
+fn check_predicate(predicate: &Predicate, ctx: &RequestContext) -> bool {
+    let mut checked = false;
+
+    if let Some(method) = &predicate.method {
+        checked = true;
+        let matched = match method {
+            ValueMatch::Exact(expected) => expected.eq_ignore_ascii_case(&ctx.method),
+            other => value_matches(other, std::slice::from_ref(&ctx.method)),
+        };
+        if !matched {
+            return false;
+        }
+    }
+
+    if let Some(path) = &predicate.path {
+        checked = true;
+        if !value_matches(path, std::slice::from_ref(&ctx.path)) {
+            return false;
+        }
+    }
+
+    if let Some(QuerySubject::Section(section)) = &predicate.query {
+        checked = true;
+        if !query_matches(section, ctx) {
+            return false;
+        }
+    }
+
+    for values in keyed_subjects(predicate, ctx) {
+        checked = true;
+        if !compare(&predicate.compare, &values) {
+            return false;
+        }
+    }
+
+    checked
+}
+
+/// Collects the values of each keyed subject (`param`, `query`, `header`,
+/// `cookie` and `body_field`) a leaf refers to. Every one of them must pass
+/// the leaf's comparison.
+fn keyed_subjects(predicate: &Predicate, ctx: &RequestContext) -> Vec<Vec<String>> {
+    let mut subjects = Vec::new();
+    if let Some(name) = &predicate.param {
+        subjects.push(ctx.params.get(name).cloned().into_iter().collect());
+    }
+    if let Some(QuerySubject::Key(name)) = &predicate.query {
+        subjects.push(ctx.query_values(name).to_vec());
+    }
+    if let Some(name) = &predicate.header {
+        subjects.push(ctx.header_values(name));
+    }
+    if let Some(name) = &predicate.cookie {
+        subjects.push(ctx.cookies.get(name).cloned().into_iter().collect());
+    }
+    if let Some(field_path) = &predicate.body_field {
+        subjects.push(
+            ctx.body
+                .as_ref()
+                .map(|body| field_path.resolve_all(body))
+                .unwrap_or_default()
+                .into_iter()
+                .map(value_to_string)
+                .collect(),
+        );
+    }
+    subjects
+}
+
+/// Checks a query section: every listed key must match.
+pub fn query_matches(section: &BTreeMap<String, ValueMatch>, ctx: &RequestContext) -> bool {
+    section
+        .iter()
+        .all(|(key, expected)| value_matches(expected, ctx.query_values(key)))
+}
+
+fn value_matches(expected: &ValueMatch, values: &[String]) -> bool {
+    match expected {
+        ValueMatch::Exact(value) => values.iter().any(|v| v == value),
+        ValueMatch::Compare(comparison) => compare(comparison, values),
+    }
+}
+
+/// Applies a comparison to the values found for a subject. A subject with
+/// several values (repeated query keys or headers) passes when any of its
+/// values passes. Without operators the comparison only asks for presence.
+fn compare(comparison: &Comparison, values: &[String]) -> bool {
+    let present = !values.is_empty();
+
+    if let Some(exists) = comparison.exists {
+        if exists != present {
+            return false;
+        }
+    }
+
+    if !present {
+        return comparison.exists == Some(false);
+    }
+
+    values.iter().any(|value| compare_one(comparison, value))
+}
+
+fn compare_one(comparison: &Comparison, value: &str) -> bool {
+    if let Some(equals) = &comparison.equals {
+        if value != equals {
+            return false;
+        }
+    }
+    if let Some(contains) = &comparison.contains {
+        if !value.contains(contains.as_str()) {
+            return false;
+        }
+    }
+    if let Some(pattern) = &comparison.matches {
+        if !pattern.is_match(value) {
+            return false;
+        }
+    }
+    if let Some(options) = &comparison.one_of {
+        if !options.iter().any(|option| option == value) {
+            return false;
+        }
+    }
+    if comparison.gt.is_some() || comparison.lt.is_some() {
+        let Ok(number) = value.trim().parse::<f64>() else {
+            return false;
+        };
+        if comparison.gt.is_some_and(|gt| number <= gt) {
+            return false;
+        }
+        if comparison.lt.is_some_and(|lt| number >= lt) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, Method, Uri};
+    use serde_json::json;
+
+    fn check(yaml: &str) -> ConditionCheck {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn request(method: Method, uri: &str, headers: &[(&str, &str)]) -> RequestContext {
+        let uri: Uri = uri.parse().unwrap();
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                name.parse::<axum::http::header::HeaderName>().unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        RequestContext::new(&method, &uri, map, None)
+    }
+
+    #[test]
+    fn test_body_field_condition() {
+        let mut ctx = request(Method::POST, "/orders", &[]);
+        ctx.body = Some(json!({ "payload": { "type": "invalid", "tags": ["a", "b"] } }));
+
+        assert!(check_condition(
+            &check(r#"{ body_field: "payload.type", equals: "invalid" }"#),
+            &ctx
+        ));
+        assert!(check_condition(
+            &check(r#"{ body_field: "/payload/tags/1", equals: "b" }"#),
+            &ctx
+        ));
+
+        assert!(check_condition(
+            &check(r#"{ body_field: "$.payload.tags[*]", equals: "b" }"#),
+            &ctx
+        ));
+
+        ctx.body = None;
+        assert!(!check_condition(
+            &check(r#"{ body_field: "payload.type", equals: "invalid" }"#),
+            &ctx
+        ));
+    }
+
+    #[test]
+    fn test_every_keyed_subject_is_checked() {
+        let mut ctx = request(Method::GET, "/users/a", &[("x-tenant", "b")]);
+        ctx.params.insert("id".to_string(), "a".to_string());
+
+        assert!(!check_condition(&check(r#"{ header: x-tenant, param: id, equals: a }"#), &ctx));
+        assert!(check_condition(&check(r#"{ header: x-tenant, param: id, in: [a, b] }"#), &ctx));
+    }
+
+    #[test]
+    fn test_inner_errors_reach_the_user() {
+        let error = |yaml: &str| serde_yaml::from_str::<ConditionCheck>(yaml).unwrap_err().to_string();
+
+        let err = error(r#"{ all: [{ header: a, matches: "([" }] }"#);
+        assert!(err.contains("invalid regex"), "{}", err);
+        let err = error(r#"{ not: { body_field: "$.a[?(@.b =~ /[/)]" } }"#);
+        assert!(err.contains("invalid path"), "{}", err);
+        let err = error(r#"{ any: [], header: a }"#);
+        assert!(err.contains("cannot be combined"), "{}", err);
+    }
+
+    #[test]
+    fn test_matches_condition() {
+        let mut ctx = request(Method::GET, "/users/usr_42", &[("x-client", "mobile-ios")]);
+        ctx.params.insert("id".to_string(), "usr_42".to_string());
+
+        assert!(check_condition(&check(r#"{ param: "id", matches: "^usr_[0-9]+$" }"#), &ctx));
+        assert!(!check_condition(&check(r#"{ header: "x-client", matches: "^web-" }"#), &ctx));
+    }
+
+    #[test]
+    fn test_all_any_not_tree() {
+        let mut ctx = request(
+            Method::POST,
+            "/api/v2/items/7?limit=50",
+            &[("x-env", "staging"), ("cookie", "session=abc")],
+        );
+        ctx.params.insert("id".to_string(), "7".to_string());
+
+        let tree = check(
+            r#"
+all:
+  - { header: "X-Env", in: [staging, prod] }
+  - { param: id, equals: 7 }
+  - any:
+      - { method: GET }
+      - { method: { in: [POST, PUT] } }
+  - not: { cookie: session, exists: false }
+  - { path: { matches: "^/api/v2/" } }
+"#,
+        );
+        assert!(check_condition(&tree, &ctx));
+
+        let not_tree = check(r#"{ not: { header: "X-Env", equals: "staging" } }"#);
+        assert!(!check_condition(&not_tree, &ctx));
+    }
+
+    #[test]
+    fn test_numeric_and_presence_operators() {
+        let ctx = request(Method::GET, "/items?limit=50&debug", &[]);
+
+        assert!(check_condition(&check(r#"{ query: limit, gt: 10, lt: 100 }"#), &ctx));
+        assert!(!check_condition(&check(r#"{ query: limit, gt: 50 }"#), &ctx));
+        assert!(check_condition(&check(r#"{ query: debug }"#), &ctx));
+        assert!(check_condition(&check(r#"{ query: page, exists: false }"#), &ctx));
+        assert!(!check_condition(&check(r#"{ query: page, equals: "1" }"#), &ctx));
+    }
+
+    #[test]
+    fn test_query_section() {
+        let ctx = request(Method::GET, "/search?page=2&q=foobar&tag=a&tag=b", &[]);
+
+        let section = check(
+            r#"
+query:
+  page: "2"
+  q: { matches: "^foo" }
+  tag: b
+  debug: { exists: false }
+"#,
+        );
+        assert!(check_condition(&section, &ctx));
+        assert!(!check_condition(&check(r#"{ query: { page: "3" } }"#), &ctx));
+
+        // Unquoted scalars match their text
+        let ctx = request(Method::GET, "/search?page=2&active=true", &[]);
+        assert!(check_condition(&check(r#"{ query: { page: 2, active: true } }"#), &ctx));
+    }
+}
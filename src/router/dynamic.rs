@@ -1,21 +1,27 @@
 use axum::{
+    body::Bytes,
     Router,
-    extract::State,
-    http::{StatusCode, HeaderMap, Method},
-    response::Json,
+    extract::{ws::WebSocketUpgrade, ConnectInfo, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
-use serde_json::{json, Value};
+use serde_json::json;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::net::SocketAddr;
 
-use crate::config::{ConfigManager, MockConfig};
-use crate::middleware::apply_delay;
-use crate::response::generate_response_body;
+use crate::config::{route_name, EndpointConfig, MockConfig, ResponseConfig};
+use crate::middleware::{apply_delay, validate_request, violations_body};
+use crate::request::RequestContext;
+use crate::response::{negotiate, render_body, render_representation};
+use crate::state::AppState;
 
-type AppState = Arc<ConfigManager>;
+use super::{
+    check_condition, handle_admin_request, handle_graphql_request, handle_resource_request, match_resource_path,
+    next_sequence_response, proxy_request, query_matches, request_rng, sse_response, websocket_response,
+};
 
 #[allow(dead_code)]
-pub fn create_dynamic_router(_config_manager: Arc<ConfigManager>) -> Router<AppState> {
+pub fn create_dynamic_router(_state: AppState) -> Router<AppState> {
     let router = Router::new();
 
     // Add a catch-all handler that processes requests dynamically
@@ -23,31 +29,64 @@ pub fn create_dynamic_router(_config_manager: Arc<ConfigManager>) -> Router<AppS
 }
 
 pub async fn handle_dynamic_request(
-    State(config_manager): State<AppState>,
+    State(state): State<AppState>,
     method: Method,
     axum::extract::OriginalUri(uri): axum::extract::OriginalUri,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    upgrade: Option<WebSocketUpgrade>,
     headers: HeaderMap,
-    body: Option<Json<Value>>,
-) -> (StatusCode, HeaderMap, Json<Value>) {
+    body: Bytes,
+) -> Response {
     let path = uri.path();
     let method_str = method.as_str();
+    let mut ctx = RequestContext::from_raw(&method, &uri, headers, body);
+    ctx.client_ip = connect_info.map(|ConnectInfo(addr)| addr.ip());
 
-    // Handle /_config endpoint for configuration management
-    if path == "/_config" {
-        return handle_config_endpoint(&config_manager, method_str, body).await;
+    // Admin endpoints: /_config, /_scenarios, /_sequences, /_requests, /_verify,
+    // /_openapi.json
+    if let Some(response) = handle_admin_request(&state, &ctx) {
+        return response.into_response();
     }
 
     tracing::info!("{} {}", method_str, path);
 
-    let config = config_manager.get_config();
+    let config = state.config.get_config();
 
     // Find matching endpoint
-    let matching_endpoint = config.endpoints.iter().find(|ep| {
-        ep.method.eq_ignore_ascii_case(method_str) && path_matches(&ep.path, path)
+    let matching_endpoint = config
+        .endpoints
+        .iter()
+        .enumerate()
+        .find(|(_, ep)| {
+            endpoint_matches(ep, method_str, path)
+                && query_matches(&ep.query, &ctx)
+                && scenario_matches(ep, &config, &state)
+        });
+
+    // Extract path parameters, before journaling so the journal has them
+    let resource_target = matching_endpoint.and_then(|(_, endpoint)| {
+        endpoint
+            .resource
+            .as_ref()
+            .and_then(|_| match_resource_path(&endpoint.path, path))
     });
+    if let Some((_, endpoint)) = matching_endpoint {
+        ctx.params = extract_path_params(&endpoint.path, path);
+        if let (Some(resource), Some(target)) = (&endpoint.resource, &resource_target) {
+            if let Some(id) = &target.id {
+                ctx.params.insert(resource.id_field.clone(), id.clone());
+            }
+        }
+    }
+
+    let endpoint_key = matching_endpoint
+        .map(|(index, ep)| format!("#{} {} {}", index + 1, ep.method, ep.path));
+    state
+        .journal
+        .record(&ctx, endpoint_key, config.server.journal_limit);
 
     match matching_endpoint {
-        Some(endpoint) => {
+        Some((_, endpoint)) => {
             // Handle timeout simulation
             if endpoint.timeout {
                 tracing::info!("Simulating timeout for {}", path);
@@ -57,68 +96,107 @@ pub async fn handle_dynamic_request(
                     StatusCode::GATEWAY_TIMEOUT,
                     HeaderMap::new(),
                     Json(json!({"error": "timeout"})),
-                );
+                )
+                    .into_response();
             }
 
+            // Seed the random values, which may depend on a path parameter
+            let route = format!("{} {}", endpoint.method, endpoint.path);
+            ctx.rng = request_rng(
+                &state.seeds,
+                config.server.seed,
+                endpoint.seed.as_ref(),
+                &route,
+                &ctx,
+            );
+
             // Apply delay if configured
             if let Some(delay) = &endpoint.delay {
-                apply_delay(delay).await;
+                apply_delay(delay, &ctx.rng).await;
             }
 
-            // Extract path parameters
-            let params = extract_path_params(&endpoint.path, path);
-
-            // Check conditions
-            for condition in &endpoint.conditions {
-                if check_condition(&condition.condition, &params, &headers, body.as_ref()) {
-                    let response_body = generate_response_body(
-                        &condition.response.body,
-                        &params,
-                        body.as_ref().map(|b| &b.0),
+            // Reject requests that break the endpoint's validation rules
+            if let Some(validation) = &endpoint.validation {
+                let violations = validate_request(validation, &ctx);
+                if !violations.is_empty() {
+                    tracing::info!(
+                        "Request to {} failed validation with {} violation(s)",
+                        path,
+                        violations.len()
                     );
-                    
-                    let mut response_headers = HeaderMap::new();
-                    for (key, value) in &condition.response.headers {
-                        if let (Ok(name), Ok(val)) = (
-                            key.parse::<axum::http::header::HeaderName>(),
-                            value.parse::<axum::http::header::HeaderValue>()
-                        ) {
-                            response_headers.insert(name, val);
-                        }
-                    }
-                    
                     return (
-                        StatusCode::from_u16(condition.response.status).unwrap_or(StatusCode::OK),
-                        response_headers,
-                        Json(response_body),
-                    );
+                        StatusCode::from_u16(validation.status).unwrap_or(StatusCode::BAD_REQUEST),
+                        HeaderMap::new(),
+                        Json(violations_body(&violations)),
+                    )
+                        .into_response();
                 }
             }
 
-            // Generate normal response
-            let response_body = generate_response_body(
-                &endpoint.response.body,
-                &params,
-                body.as_ref().map(|b| &b.0),
-            );
+            // The request is accepted, so move the endpoint's scenario along
+            if let Some(step) = &endpoint.scenario {
+                if let (Some(to), Some(scenario)) =
+                    (&step.transition, config.scenarios.get(&step.name))
+                {
+                    if state.scenarios.advance(&step.name, scenario, step.state.as_deref(), to) {
+                        tracing::info!("Scenario {} moved to \"{}\"", step.name, to);
+                    }
+                }
+            }
 
-            let mut response_headers = HeaderMap::new();
-            for (key, value) in &endpoint.response.headers {
-                if let (Ok(name), Ok(val)) = (
-                    key.parse::<axum::http::header::HeaderName>(),
-                    value.parse::<axum::http::header::HeaderValue>()
-                ) {
-                    response_headers.insert(name, val);
+            // Hand the connection over to the WebSocket script
+            if let Some(script) = &endpoint.websocket {
+                let Some(upgrade) = upgrade else {
+                    return (
+                        StatusCode::UPGRADE_REQUIRED,
+                        [(header::UPGRADE, "websocket")],
+                        Json(json!({"error": "This endpoint only accepts WebSocket connections"})),
+                    )
+                        .into_response();
+                };
+                return websocket_response(upgrade, script, &ctx);
+            }
+
+            // Answer GraphQL queries from the schema
+            if let Some(graphql) = &endpoint.graphql {
+                return handle_graphql_request(graphql, &ctx).into_response();
+            }
+
+            // Check conditions
+            for condition in &endpoint.conditions {
+                if check_condition(&condition.condition, &ctx) {
+                    return render_response(&condition.response, &ctx);
                 }
             }
 
-            (
-                StatusCode::from_u16(endpoint.response.status).unwrap_or(StatusCode::OK),
-                response_headers,
-                Json(response_body),
-            )
+            // Answer from the real upstream
+            if let Some(proxy) = &endpoint.proxy {
+                return proxy_request(&state.http_client, proxy, &ctx, uri.query()).await;
+            }
+
+            // Serve the CRUD collection behind a resource endpoint
+            if let (Some(resource), Some(target)) = (&endpoint.resource, &resource_target) {
+                return handle_resource_request(&state.resources, resource, target, &ctx)
+                    .into_response();
+            }
+
+            // Serve the next of the endpoint's responses, if it has several.
+            // Counters are keyed by route so they follow it across reloads
+            let route_name = route_name(endpoint);
+            if let Some(response) =
+                next_sequence_response(&state.sequences, &route_name, endpoint, &ctx)
+            {
+                return render_response(response, &ctx);
+            }
+
+            // Generate normal response
+            render_response(&endpoint.response, &ctx)
         }
         None => {
+            if let Some(proxy) = &config.proxy {
+                tracing::info!("No matching endpoint for {} {}, proxying", method_str, path);
+                return proxy_request(&state.http_client, proxy, &ctx, uri.query()).await;
+            }
             tracing::warn!("No matching endpoint found for {} {}", method_str, path);
             (
                 StatusCode::NOT_FOUND,
@@ -129,11 +207,107 @@ pub async fn handle_dynamic_request(
                     "method": method_str
                 })),
             )
+                .into_response()
         }
     }
 }
 
-fn path_matches(pattern: &str, actual_path: &str) -> bool {
+fn render_response(response: &ResponseConfig, ctx: &RequestContext) -> Response {
+    if let Some(sse) = &response.sse {
+        let mut stream = sse_response(sse, ctx);
+        *stream.status_mut() = response.status.resolve(ctx);
+        stream.headers_mut().extend(render_headers(response, ctx));
+        return stream;
+    }
+
+    let mut response_headers = HeaderMap::new();
+
+    let rendered = if response.representations.is_empty() {
+        render_body(response, ctx)
+    } else {
+        // Pick the representation the client accepts best
+        let offered: Vec<&str> = response
+            .representations
+            .iter()
+            .map(|r| r.media_type.as_str())
+            .collect();
+        let accept = ctx.header_values("accept").join(",");
+        response_headers.insert(header::VARY, HeaderValue::from_static("accept"));
+        let Some(index) = negotiate(Some(accept.as_str()), &offered) else {
+            return (
+                StatusCode::NOT_ACCEPTABLE,
+                response_headers,
+                Json(json!({
+                    "error": "Not acceptable",
+                    "accept": accept,
+                    "available": offered
+                })),
+            )
+                .into_response();
+        };
+        render_representation(response, &response.representations[index], ctx)
+    };
+
+    let body = match rendered {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to render response body: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Failed to render response body",
+                    "details": e
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    response_headers.extend(render_headers(response, ctx));
+    body.into_response(response.status.resolve(ctx), response_headers)
+}
+
+fn render_headers(response: &ResponseConfig, ctx: &RequestContext) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (key, value) in &response.headers {
+        if let (Ok(name), Ok(val)) = (
+            key.parse::<axum::http::header::HeaderName>(),
+            value.render(ctx).parse::<axum::http::header::HeaderValue>()
+        ) {
+            headers.insert(name, val);
+        }
+    }
+    headers
+}
+
+fn endpoint_matches(endpoint: &EndpointConfig, method: &str, path: &str) -> bool {
+    if endpoint.resource.is_some() {
+        // Resources answer every method on their base path and item paths
+        return match_resource_path(&endpoint.path, path).is_some();
+    }
+    if endpoint.graphql.is_some() {
+        // GraphQL queries arrive as GET or POST
+        return matches!(method, "GET" | "POST") && path_matches(&endpoint.path, path);
+    }
+    endpoint.method.eq_ignore_ascii_case(method) && path_matches(&endpoint.path, path)
+}
+
+/// An endpoint tied to a scenario state only matches while the scenario is
+/// in that state.
+fn scenario_matches(endpoint: &EndpointConfig, config: &MockConfig, state: &AppState) -> bool {
+    let Some(step) = &endpoint.scenario else {
+        return true;
+    };
+    let Some(required) = &step.state else {
+        return true;
+    };
+    config
+        .scenarios
+        .get(&step.name)
+        .is_some_and(|scenario| &state.scenarios.state(&step.name, scenario) == required)
+}
+
+pub fn path_matches(pattern: &str, actual_path: &str) -> bool {
     
     let pattern_parts: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
     let actual_parts: Vec<&str> = actual_path.split('/').filter(|s| !s.is_empty()).collect();
@@ -170,106 +344,108 @@ fn extract_path_params(pattern: &str, actual_path: &str) -> HashMap<String, Stri
     params
 }
 
-fn check_condition(
-    condition: &crate::config::ConditionCheck,
-    params: &HashMap<String, String>,
-    headers: &HeaderMap,
-    _body: Option<&Json<Value>>,
-) -> bool {
-    // Check param condition
-    if let Some(param_name) = &condition.param {
-        if let Some(param_value) = params.get(param_name) {
-            if let Some(equals) = &condition.equals {
-                return param_value == equals;
-            }
-            if let Some(contains) = &condition.contains {
-                return param_value.contains(contains);
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigManager;
+    use axum::extract::OriginalUri;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    async fn state(yaml: &str) -> (tempfile::TempDir, AppState) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("endpoints.yaml");
+        std::fs::write(&path, yaml).unwrap();
+        let config = ConfigManager::new(path.to_str().unwrap()).await.unwrap();
+        (dir, AppState::new(Arc::new(config)))
     }
 
-    // Check header condition
-    if let Some(header_name) = &condition.header {
-        if let Some(header_value) = headers.get(header_name).and_then(|v| v.to_str().ok()) {
-            if let Some(equals) = &condition.equals {
-                return header_value == equals;
-            }
-            if let Some(contains) = &condition.contains {
-                return header_value.contains(contains);
-            }
-        }
+    async fn send(state: &AppState, method: Method, uri: &str, headers: HeaderMap, body: Value) -> Response {
+        handle_dynamic_request(
+            State(state.clone()),
+            method,
+            OriginalUri(uri.parse().unwrap()),
+            None,
+            None,
+            headers,
+            Bytes::from(body.to_string()),
+        )
+        .await
     }
 
-    false
-}
+    #[tokio::test]
+    async fn test_seeded_posts_get_distinct_ids() {
+        let (_dir, state) = state(
+            r#"
+server:
+  seed: 42
+endpoints:
+  - path: "/users"
+    resource:
+      seed: [{ id: "a4b1", name: "Ana" }]
+"#,
+        )
+        .await;
 
-async fn handle_config_endpoint(
-    config_manager: &Arc<ConfigManager>,
-    method: &str,
-    body: Option<Json<Value>>,
-) -> (StatusCode, HeaderMap, Json<Value>) {
-    match method {
-        "GET" => {
-            // Return current configuration
-            let config = config_manager.get_config();
-            let config_json = serde_json::to_value(&config).unwrap_or(json!({}));
-            (
-                StatusCode::OK,
-                HeaderMap::new(),
-                Json(json!({
-                    "success": true,
-                    "config": config_json
-                })),
-            )
-        }
-        "POST" | "PUT" => {
-            // Update configuration from request body
-            match body {
-                Some(Json(config_value)) => {
-                    match serde_json::from_value::<MockConfig>(config_value) {
-                        Ok(new_config) => {
-                            config_manager.update_config(new_config);
-                            tracing::info!("Configuration updated via HTTP");
-                            (
-                                StatusCode::OK,
-                                HeaderMap::new(),
-                                Json(json!({
-                                    "success": true,
-                                    "message": "Configuration updated successfully",
-                                    "endpoints_count": config_manager.get_config().endpoints.len()
-                                })),
-                            )
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to parse config: {}", e);
-                            (
-                                StatusCode::BAD_REQUEST,
-                                HeaderMap::new(),
-                                Json(json!({
-                                    "success": false,
-                                    "error": format!("Invalid configuration: {}", e)
-                                })),
-                            )
-                        }
-                    }
+        for seed_header in [None, Some("7")] {
+            let mut ids = Vec::new();
+            for name in ["Bruno", "Carla"] {
+                let mut headers = HeaderMap::new();
+                headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                if let Some(seed) = seed_header {
+                    headers.insert(crate::router::SEED_HEADER, HeaderValue::from_static(seed));
                 }
-                None => (
-                    StatusCode::BAD_REQUEST,
-                    HeaderMap::new(),
-                    Json(json!({
-                        "success": false,
-                        "error": "Request body is required"
-                    })),
-                ),
+                let response = send(&state, Method::POST, "/users", headers, json!({ "name": name })).await;
+                assert_eq!(response.status(), StatusCode::CREATED);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let item: Value = serde_json::from_slice(&body).unwrap();
+                ids.push(item["id"].clone());
             }
+            assert_ne!(ids[0], ids[1]);
         }
-        _ => (
-            StatusCode::METHOD_NOT_ALLOWED,
-            HeaderMap::new(),
-            Json(json!({
-                "success": false,
-                "error": "Method not allowed. Use GET to view config or POST/PUT to update."
-            })),
-        ),
+    }
+
+    #[tokio::test]
+    async fn test_journal_has_path_params() {
+        let (_dir, state) = state(
+            r#"
+endpoints:
+  - path: "/users/:id"
+    response:
+      body: { id: "{{param.id}}" }
+"#,
+        )
+        .await;
+
+        send(&state, Method::GET, "/users/42", HeaderMap::new(), Value::Null).await;
+        let entries = state.journal.entries();
+        assert_eq!(entries[0].request.params["id"], "42");
+    }
+
+    #[tokio::test]
+    async fn test_sequences_follow_their_route_across_reloads() {
+        let (_dir, state) = state(
+            r#"
+endpoints:
+  - path: "/jobs/:id"
+    responses:
+      - { status: 202, body: null }
+      - { status: 200, body: null }
+"#,
+        )
+        .await;
+
+        let response = send(&state, Method::GET, "/jobs/1", HeaderMap::new(), Value::Null).await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        // An endpoint added in front moves the sequence down the list
+        let mut config = state.config.get_config();
+        let health: EndpointConfig = serde_yaml::from_str("{ path: /health, response: { body: null } }").unwrap();
+        config.endpoints.insert(0, health);
+        state.config.update_config(config);
+
+        let response = send(&state, Method::GET, "/jobs/1", HeaderMap::new(), Value::Null).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(state.sequences.snapshot().contains_key("GET /jobs/:"));
     }
 }
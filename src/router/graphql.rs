@@ -0,0 +1,67 @@
+use axum::{http::StatusCode, response::Json};
+use serde_json::{Map, Value};
+
+use crate::config::GraphQLConfig;
+use crate::graphql::{errors_response, execute, GraphQLError, GraphQLRequest};
+use crate::request::RequestContext;
+
+/// Answers a GraphQL request sent as a POST body (JSON or
+/// `application/graphql`) or as GET query parameters.
+pub fn handle_graphql_request(
+    config: &GraphQLConfig,
+    ctx: &RequestContext,
+) -> (StatusCode, Json<Value>) {
+    let schema = match config.parsed_schema() {
+        Ok(schema) => schema,
+        Err(e) => {
+            tracing::error!("Failed to load GraphQL schema: {}", e);
+            let error = GraphQLError::new(format!("Failed to load GraphQL schema: {}", e), None);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(errors_response(&[error])));
+        }
+    };
+
+    let Some(request) = graphql_request(ctx) else {
+        let error = GraphQLError::new("Must provide query string.", None);
+        return (StatusCode::BAD_REQUEST, Json(errors_response(&[error])));
+    };
+
+    (StatusCode::OK, Json(execute(&schema, config, &request, ctx)))
+}
+
+fn graphql_request(ctx: &RequestContext) -> Option<GraphQLRequest> {
+    if ctx.method.eq_ignore_ascii_case("GET") {
+        let first = |key: &str| ctx.query_values(key).first().cloned();
+        return Some(GraphQLRequest {
+            query: first("query")?,
+            variables: first("variables")
+                .and_then(|variables| serde_json::from_str(&variables).ok())
+                .unwrap_or_default(),
+            operation_name: first("operationName"),
+        });
+    }
+
+    let is_graphql = ctx
+        .header_values("content-type")
+        .iter()
+        .any(|value| value.starts_with("application/graphql"));
+    if is_graphql {
+        return Some(GraphQLRequest {
+            query: String::from_utf8(ctx.raw_body.to_vec()).ok()?,
+            ..Default::default()
+        });
+    }
+
+    let body = ctx.body.as_ref()?;
+    Some(GraphQLRequest {
+        query: body.get("query")?.as_str()?.to_string(),
+        variables: body
+            .get("variables")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_else(Map::new),
+        operation_name: body
+            .get("operationName")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
+}
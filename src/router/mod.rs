@@ -1,3 +1,21 @@
+mod admin;
+mod conditions;
 mod dynamic;
+mod graphql;
+mod proxy;
+mod resources;
+mod seed;
+mod sequences;
+mod sse;
+mod websocket;
 
+pub use admin::*;
+pub use conditions::*;
 pub use dynamic::*;
+pub use graphql::*;
+pub use proxy::*;
+pub use resources::*;
+pub use seed::*;
+pub use sequences::*;
+pub use sse::*;
+pub use websocket::*;
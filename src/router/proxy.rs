@@ -0,0 +1,314 @@
+use axum::{
+    body::Bytes,
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use base64::Engine;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::config::{
+    record_endpoint, EndpointConfig, ProxyConfig, ResponseConfig, ResponseStatus, ValueMatch,
+};
+use crate::request::RequestContext;
+use crate::response::{BodyTemplate, Template};
+
+/// Headers that only make sense for a single connection, or that the proxy
+/// sets itself, and are therefore never passed along.
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+    "accept-encoding",
+    "content-encoding",
+    // CORS headers come from the mock's own CORS layer
+    "vary",
+    "access-control-allow-origin",
+    "access-control-allow-credentials",
+    "access-control-allow-headers",
+    "access-control-allow-methods",
+    "access-control-expose-headers",
+    "access-control-max-age",
+];
+
+/// Response headers left out of recordings because they change on every
+/// exchange.
+const VOLATILE: &[&str] = &["date", "server", "etag", "last-modified"];
+
+struct UpstreamResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Forwards a request to the proxy's upstream and answers with its response,
+/// recording the exchange when the proxy has a `record` file. Upstream
+/// failures become a 502.
+pub async fn proxy_request(
+    client: &reqwest::Client,
+    proxy: &ProxyConfig,
+    ctx: &RequestContext,
+    raw_query: Option<&str>,
+) -> Response {
+    let upstream = match forward(client, proxy.url(), ctx, raw_query).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            tracing::error!("Proxying {} {} failed: {}", ctx.method, ctx.path, e);
+            return (
+                StatusCode::BAD_GATEWAY,
+                HeaderMap::new(),
+                Json(json!({
+                    "error": "Upstream request failed",
+                    "upstream": proxy.url(),
+                    "details": e.to_string()
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    if let Some(file) = proxy.record() {
+        let endpoint = recorded_endpoint(ctx, &upstream);
+        let target = file.to_path_buf();
+        let recorded = tokio::task::spawn_blocking(move || record_endpoint(&target, endpoint))
+            .await
+            .unwrap_or_else(|e| Err(e.into()));
+        match recorded {
+            Ok(true) => tracing::info!("Recorded {} {} to {}", ctx.method, ctx.path, file.display()),
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to record to {}: {}", file.display(), e),
+        }
+    }
+
+    (upstream.status, upstream.headers, upstream.body).into_response()
+}
+
+async fn forward(
+    client: &reqwest::Client,
+    base_url: &str,
+    ctx: &RequestContext,
+    raw_query: Option<&str>,
+) -> Result<UpstreamResponse, reqwest::Error> {
+    let mut url = format!("{}{}", base_url.trim_end_matches('/'), ctx.path);
+    if let Some(query) = raw_query.filter(|q| !q.is_empty()) {
+        url = format!("{}?{}", url, query);
+    }
+
+    let method = reqwest::Method::from_bytes(ctx.method.as_bytes()).unwrap_or(reqwest::Method::GET);
+    let mut request = client.request(method, &url).headers(passable(&ctx.headers));
+    if !ctx.raw_body.is_empty() {
+        request = request.body(ctx.raw_body.clone());
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    let headers = passable(response.headers());
+    let body = response.bytes().await?;
+
+    Ok(UpstreamResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn passable(headers: &HeaderMap) -> HeaderMap {
+    headers
+        .iter()
+        .filter(|(name, _)| !HOP_BY_HOP.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// Turns an upstream exchange into an endpoint that replays it.
+fn recorded_endpoint(ctx: &RequestContext, upstream: &UpstreamResponse) -> EndpointConfig {
+    let query = ctx
+        .query
+        .iter()
+        .filter_map(|(key, values)| {
+            values
+                .first()
+                .map(|value| (key.clone(), ValueMatch::Exact(value.clone())))
+        })
+        .collect();
+
+    let headers: HashMap<String, Template> = upstream
+        .headers
+        .iter()
+        .filter(|(name, _)| !VOLATILE.contains(&name.as_str()) && *name != header::CONTENT_TYPE)
+        .filter_map(|(name, value): (&HeaderName, _)| {
+            let template = Template::parse(&Template::escape(value.to_str().ok()?)).ok()?;
+            Some((name.to_string(), template))
+        })
+        .collect();
+
+    let mut response = ResponseConfig {
+        status: ResponseStatus::Code(upstream.status.as_u16()),
+        headers,
+        ..Default::default()
+    };
+    let content_type = upstream
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let is_json = content_type
+        .as_deref()
+        .is_none_or(|ct| ct.contains("json"));
+
+    // JSON bodies are replayed as JSON; text and binary keep their type
+    match serde_json::from_slice::<Value>(&upstream.body) {
+        _ if upstream.body.is_empty() => {}
+        Ok(body) if is_json => {
+            response.body = BodyTemplate::new(escape_strings(body))
+                .expect("escaped strings are valid templates")
+        }
+        _ => {
+            response.content_type = content_type;
+            match std::str::from_utf8(&upstream.body) {
+                Ok(text) => {
+                    response.text = Some(
+                        Template::parse(&Template::escape(text))
+                            .expect("escaped text is a valid template"),
+                    )
+                }
+                Err(_) => {
+                    response.base64 =
+                        Some(base64::engine::general_purpose::STANDARD.encode(&upstream.body))
+                }
+            }
+        }
+    }
+
+    EndpointConfig {
+        path: ctx.path.clone(),
+        method: ctx.method.clone(),
+        query,
+        response,
+        ..Default::default()
+    }
+}
+
+/// Escapes placeholders in every string of a recorded body, so the replay
+/// sends what the upstream did.
+fn escape_strings(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(Template::escape(&text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(escape_strings).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, escape_strings(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, routing::get, Router};
+    use axum::http::{Method, Uri};
+
+    async fn upstream() -> String {
+        let app = Router::new().route(
+            "/users",
+            get(|Query(query): Query<HashMap<String, String>>| async move {
+                (
+                    [("x-upstream", "legacy")],
+                    Json(json!([{ "id": 1, "role": query.get("role") }])),
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_proxy_records_loadable_endpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("recorded.yaml");
+        let proxy = ProxyConfig::Target {
+            url: upstream().await,
+            record: Some(file.clone()),
+        };
+
+        let uri: Uri = "/users?role=admin".parse().unwrap();
+        let ctx = RequestContext::new(&Method::GET, &uri, HeaderMap::new(), None);
+        let client = reqwest::Client::new();
+        for _ in 0..2 {
+            let response = proxy_request(&client, &proxy, &ctx, uri.query()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["x-upstream"], "legacy");
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), json!([{ "id": 1, "role": "admin" }]));
+        }
+
+        // Recorded once, in place, and loadable as a regular config file
+        assert!(!dir.path().join("recorded.yaml.tmp").exists());
+        let config = crate::config::load_config(file.to_str().unwrap()).unwrap();
+        assert_eq!(config.endpoints.len(), 1);
+        let endpoint = &config.endpoints[0];
+        assert_eq!((endpoint.method.as_str(), endpoint.path.as_str()), ("GET", "/users"));
+        assert!(matches!(&endpoint.query["role"], ValueMatch::Exact(v) if v == "admin"));
+        assert_eq!(endpoint.response.body.value(), &json!([{ "id": 1, "role": "admin" }]));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_upstream_is_a_bad_gateway() {
+        let proxy = ProxyConfig::Url("http://127.0.0.1:9".to_string());
+        let ctx = RequestContext::default();
+        let response = proxy_request(&reqwest::Client::new(), &proxy, &ctx, None).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[test]
+    fn test_recorded_placeholders_replay_as_text() {
+        let upstream = UpstreamResponse {
+            status: StatusCode::OK,
+            headers: [(HeaderName::from_static("x-note"), "{{query.id}}".parse().unwrap())]
+                .into_iter()
+                .collect(),
+            body: Bytes::from(json!({ "note": "{{ oops", "hint": "{{query.id}}" }).to_string()),
+        };
+        let recorded = recorded_endpoint(&RequestContext::default(), &upstream);
+
+        // Survives a round trip through the recording file
+        let yaml = serde_yaml::to_string(&crate::config::MockConfig {
+            endpoints: vec![recorded],
+            ..Default::default()
+        })
+        .unwrap();
+        let config: crate::config::MockConfig = serde_yaml::from_str(&yaml).unwrap();
+        let response = &config.endpoints[0].response;
+
+        let uri: Uri = "/?id=7".parse().unwrap();
+        let ctx = RequestContext::new(&Method::GET, &uri, HeaderMap::new(), None);
+        assert_eq!(
+            crate::response::generate_response_body(&response.body, &ctx),
+            json!({ "note": "{{ oops", "hint": "{{query.id}}" })
+        );
+        assert_eq!(response.headers["x-note"].render(&ctx), "{{query.id}}");
+
+        let text = UpstreamResponse {
+            status: StatusCode::OK,
+            headers: [(header::CONTENT_TYPE, "text/plain".parse().unwrap())]
+                .into_iter()
+                .collect(),
+            body: Bytes::from_static(b"{{ oops and {{query.id}}"),
+        };
+        let response = recorded_endpoint(&RequestContext::default(), &text).response;
+        assert!(response.base64.is_none());
+        assert_eq!(response.text.unwrap().render(&ctx), "{{ oops and {{query.id}}");
+    }
+}
@@ -0,0 +1,124 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Json,
+};
+use serde_json::{json, Value};
+
+use crate::config::ResourceConfig;
+use crate::request::RequestContext;
+use crate::response::value_to_string;
+use crate::state::{ResourceError, ResourceStore};
+
+/// Where a request lands inside a resource: the collection itself or one
+/// item of it.
+pub struct ResourceTarget {
+    pub base_path: String,
+    pub id: Option<String>,
+}
+
+/// Matches a request path against a resource base path such as
+/// `/api/users` or `/orgs/:org/users`, accepting the base path itself and
+/// one extra segment for the item id.
+pub fn match_resource_path(pattern: &str, actual_path: &str) -> Option<ResourceTarget> {
+    let pattern_parts: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let actual_parts: Vec<&str> = actual_path.split('/').filter(|s| !s.is_empty()).collect();
+
+    if actual_parts.len() != pattern_parts.len() && actual_parts.len() != pattern_parts.len() + 1 {
+        return None;
+    }
+
+    for (pattern_part, actual_part) in pattern_parts.iter().zip(actual_parts.iter()) {
+        if !pattern_part.starts_with(':') && pattern_part != actual_part {
+            return None;
+        }
+    }
+
+    Some(ResourceTarget {
+        base_path: format!("/{}", actual_parts[..pattern_parts.len()].join("/")),
+        id: actual_parts.get(pattern_parts.len()).map(|id| id.to_string()),
+    })
+}
+
+pub fn handle_resource_request(
+    store: &ResourceStore,
+    resource: &ResourceConfig,
+    target: &ResourceTarget,
+    ctx: &RequestContext,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    let mut headers = HeaderMap::new();
+    let body = ctx.body.clone().unwrap_or(Value::Null);
+
+    let result = store.with_collection(&target.base_path, resource, &ctx.rng, |collection| {
+        match (ctx.method.as_str(), target.id.as_deref()) {
+            ("GET", None) => {
+                let page = collection.list(&ctx.query);
+                headers.insert("x-total-count", HeaderValue::from(page.total));
+                Ok((StatusCode::OK, Value::Array(page.items)))
+            }
+            ("GET", Some(id)) => collection.get(id).map(|item| (StatusCode::OK, item)),
+            ("POST", None) => collection.create(body).map(|item| {
+                let id = item
+                    .get(&resource.id_field)
+                    .map(value_to_string)
+                    .unwrap_or_default();
+                if let Ok(location) = format!("{}/{}", target.base_path, id).parse() {
+                    headers.insert(header::LOCATION, location);
+                }
+                (StatusCode::CREATED, item)
+            }),
+            ("PUT", Some(id)) => collection.replace(id, body).map(|item| (StatusCode::OK, item)),
+            ("PATCH", Some(id)) => collection.patch(id, body).map(|item| (StatusCode::OK, item)),
+            ("DELETE", Some(id)) => collection.delete(id).map(|item| (StatusCode::OK, item)),
+            _ => Err(ResourceError::MethodNotAllowed),
+        }
+    });
+
+    match result {
+        Ok((status, item)) => (status, headers, Json(item)),
+        Err(ResourceError::NotFound) => (
+            StatusCode::NOT_FOUND,
+            headers,
+            Json(json!({
+                "error": "Resource not found",
+                "id": target.id
+            })),
+        ),
+        Err(ResourceError::Conflict(message)) => (
+            StatusCode::CONFLICT,
+            headers,
+            Json(json!({ "error": message })),
+        ),
+        Err(ResourceError::MethodNotAllowed) => (
+            StatusCode::METHOD_NOT_ALLOWED,
+            headers,
+            Json(json!({
+                "error": "Method not allowed on this resource path",
+                "method": ctx.method,
+                "path": ctx.path
+            })),
+        ),
+        Err(ResourceError::BadRequest(message)) => (
+            StatusCode::BAD_REQUEST,
+            headers,
+            Json(json!({ "error": message })),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_resource_path() {
+        let target = match_resource_path("/orgs/:org/users", "/orgs/7/users/42").unwrap();
+        assert_eq!(target.base_path, "/orgs/7/users");
+        assert_eq!(target.id.as_deref(), Some("42"));
+
+        let target = match_resource_path("/api/users", "/api/users").unwrap();
+        assert_eq!(target.id, None);
+
+        assert!(match_resource_path("/api/users", "/api/users/1/posts").is_none());
+        assert!(match_resource_path("/api/users", "/api/teams").is_none());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::config::SeedConfig;
+use crate::request::{seed_from, RequestContext, RequestRng};
+use crate::state::SeedStreams;
+
+/// Seeds a single request, overriding the configured seeds.
+pub const SEED_HEADER: &str = "x-mock-seed";
+
+/// The generator for a request to `route` (such as `GET /users/:id`). The
+/// `X-Mock-Seed` header wins over the endpoint's seed, which wins over the
+/// server's; with none of them the values are random. A per-param seed
+/// mixes the parameter's value into whichever of those applies.
+///
+/// Configured seeds draw from a stream kept for the whole run, so requests
+/// differ from each other but the run as a whole repeats. The header and
+/// per-param seeds instead pin the single response, which is their point.
+pub fn request_rng(
+    streams: &SeedStreams,
+    server_seed: Option<u64>,
+    endpoint_seed: Option<&SeedConfig>,
+    route: &str,
+    ctx: &RequestContext,
+) -> RequestRng {
+    let fixed = match endpoint_seed {
+        Some(SeedConfig::Fixed(seed)) => Some(*seed),
+        _ => server_seed,
+    };
+    let header = ctx.header_values(SEED_HEADER).into_iter().next();
+    let param = match endpoint_seed {
+        Some(SeedConfig::PerParam { param }) => Some(
+            ctx.params
+                .get(param)
+                .map(String::as_str)
+                .unwrap_or_default(),
+        ),
+        _ => None,
+    };
+
+    let base = header.clone().or_else(|| fixed.map(|seed| seed.to_string()));
+    let seed = || {
+        seed_from(&[
+            base.as_deref().unwrap_or("0"),
+            route,
+            param.unwrap_or_default(),
+        ])
+    };
+    match (header, fixed, param) {
+        (None, None, None) => RequestRng::from_entropy(),
+        (None, Some(_), None) => streams.stream(seed()),
+        _ => RequestRng::seeded(seed()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{generate_response_body, BodyTemplate};
+    use serde_json::{json, Value};
+
+    fn user(
+        server_seed: Option<u64>,
+        endpoint_seed: Option<SeedConfig>,
+        id: &str,
+        header: Option<&str>,
+    ) -> Value {
+        let mut ctx = RequestContext::default();
+        ctx.params.insert("id".to_string(), id.to_string());
+        if let Some(seed) = header {
+            ctx.headers.insert(SEED_HEADER, seed.parse().unwrap());
+        }
+        ctx.rng = request_rng(
+            &SeedStreams::default(),
+            server_seed,
+            endpoint_seed.as_ref(),
+            "GET /users/:id",
+            &ctx,
+        );
+
+        let template = BodyTemplate::new(json!({
+            "id": { "$fake": "uuid" },
+            "name": { "$fake": "name" },
+            "greeting": "Hi {{fake.firstName}}",
+            "tags": { "$array": { "count": 2, "template": { "$fake": "word" } } },
+            "age": { "$schema": { "type": "integer", "minimum": 18, "maximum": 99 } }
+        }))
+        .unwrap();
+        generate_response_body(&template, &ctx)
+    }
+
+    #[test]
+    fn test_seeds_make_responses_repeatable() {
+        assert_eq!(
+            user(Some(1), None, "1", None),
+            user(Some(1), None, "2", None)
+        );
+        assert_ne!(
+            user(Some(1), None, "1", None),
+            user(Some(2), None, "1", None)
+        );
+        assert_ne!(user(None, None, "1", None), user(None, None, "1", None));
+
+        // The endpoint's seed wins over the server's, the header over both
+        let fixed = Some(SeedConfig::Fixed(9));
+        assert_eq!(
+            user(Some(1), fixed.clone(), "1", None),
+            user(Some(2), fixed.clone(), "1", None)
+        );
+        assert_eq!(
+            user(Some(1), fixed, "1", Some("5")),
+            user(None, None, "1", Some("5"))
+        );
+        assert_eq!(
+            user(Some(5), None, "1", None),
+            user(None, None, "1", Some("5"))
+        );
+    }
+
+    #[test]
+    fn test_per_param_seed_is_stable_per_value() {
+        let per_id = || {
+            Some(SeedConfig::PerParam {
+                param: "id".to_string(),
+            })
+        };
+        assert_eq!(
+            user(None, per_id(), "42", None),
+            user(None, per_id(), "42", None)
+        );
+        assert_ne!(
+            user(None, per_id(), "42", None),
+            user(None, per_id(), "43", None)
+        );
+        assert_ne!(
+            user(Some(1), per_id(), "42", None),
+            user(Some(2), per_id(), "42", None)
+        );
+    }
+
+    #[test]
+    fn test_configured_seeds_repeat_per_run_not_per_request() {
+        let draws = |streams: &SeedStreams| -> Vec<u64> {
+            (0..3)
+                .map(|_| {
+                    request_rng(streams, Some(1), None, "GET /rand", &RequestContext::default())
+                        .with(rand::Rng::gen::<u64>)
+                })
+                .collect()
+        };
+        let run = draws(&SeedStreams::default());
+        assert_ne!(run[0], run[1]);
+        assert_eq!(run, draws(&SeedStreams::default()));
+    }
+}
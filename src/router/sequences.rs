@@ -0,0 +1,147 @@
+use rand::Rng;
+
+use crate::config::{ClientAddress, ClientKey, EndpointConfig, ResponseConfig, SequenceEntry, SequenceMode};
+use crate::request::RequestContext;
+use crate::state::SequenceStore;
+
+/// Picks the response an endpoint with `responses` serves for this request
+/// and advances its counter. `endpoint_key` identifies the endpoint in the
+/// store.
+pub fn next_sequence_response<'a>(
+    store: &SequenceStore,
+    endpoint_key: &str,
+    endpoint: &'a EndpointConfig,
+    ctx: &RequestContext,
+) -> Option<&'a ResponseConfig> {
+    if endpoint.responses.is_empty() {
+        return None;
+    }
+    let sequence = endpoint.sequence.clone().unwrap_or_default();
+
+    let index = match sequence.mode {
+        SequenceMode::Random => ctx.rng.with(|rng| pick_weighted(&endpoint.responses, rng)),
+        mode => {
+            let key = match &sequence.per_client {
+                Some(client) => format!("{} [{}]", endpoint_key, client_id(client, ctx)),
+                None => endpoint_key.to_string(),
+            };
+            position(mode, store.next(&key), endpoint.responses.len())
+        }
+    };
+
+    Some(&endpoint.responses[index].response)
+}
+
+fn client_id(client: &ClientKey, ctx: &RequestContext) -> String {
+    match client {
+        ClientKey::Address(ClientAddress::Ip) => ctx.client_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+        ClientKey::Header { header } => ctx.header_values(header).into_iter().next().unwrap_or_default(),
+    }
+}
+
+/// Index of the response served on the `count`-th call (0-based).
+fn position(mode: SequenceMode, count: usize, len: usize) -> usize {
+    match mode {
+        SequenceMode::Cycle => count % len,
+        _ => count.min(len - 1),
+    }
+}
+
+fn pick_weighted(entries: &[SequenceEntry], rng: &mut impl Rng) -> usize {
+    let total: u64 = entries.iter().map(|e| u64::from(e.weight)).sum();
+    if total == 0 {
+        return rng.gen_range(0..entries.len());
+    }
+    let mut roll = rng.gen_range(0..total);
+    for (index, entry) in entries.iter().enumerate() {
+        let weight = u64::from(entry.weight);
+        if roll < weight {
+            return index;
+        }
+        roll -= weight;
+    }
+    entries.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SequenceConfig;
+    use axum::http::HeaderValue;
+
+    fn endpoint(yaml: &str) -> EndpointConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn status(response: Option<&ResponseConfig>) -> u16 {
+        response.unwrap().status.resolve(&RequestContext::default()).as_u16()
+    }
+
+    #[test]
+    fn test_sequential_and_cycle() {
+        let store = SequenceStore::default();
+        let ctx = RequestContext::default();
+        let sequential = endpoint(
+            r#"
+path: /job
+responses:
+  - { status: 202, body: null }
+  - { status: 200, body: null }
+"#,
+        );
+        let served: Vec<u16> = (0..3)
+            .map(|_| status(next_sequence_response(&store, "job", &sequential, &ctx)))
+            .collect();
+        assert_eq!(served, vec![202, 200, 200]);
+
+        let per_ip: SequenceConfig = serde_yaml::from_str("per_client: ip").unwrap();
+        assert!(matches!(per_ip.per_client, Some(ClientKey::Address(ClientAddress::Ip))));
+
+        let mut cycle = sequential.clone();
+        cycle.sequence = serde_yaml::from_str("mode: cycle").unwrap();
+        let served: Vec<u16> = (0..3)
+            .map(|_| status(next_sequence_response(&store, "cycle", &cycle, &ctx)))
+            .collect();
+        assert_eq!(served, vec![202, 200, 202]);
+
+        store.reset();
+        assert_eq!(status(next_sequence_response(&store, "job", &sequential, &ctx)), 202);
+    }
+
+    #[test]
+    fn test_per_client_counters() {
+        let store = SequenceStore::default();
+        let endpoint = endpoint(
+            r#"
+path: /retry
+sequence:
+  per_client: { header: X-Client-Id }
+responses:
+  - { status: 503, body: null }
+  - { status: 200, body: null }
+"#,
+        );
+        let client = |id: &'static str| {
+            let mut ctx = RequestContext::default();
+            ctx.headers.insert("x-client-id", HeaderValue::from_static(id));
+            ctx
+        };
+
+        assert_eq!(status(next_sequence_response(&store, "retry", &endpoint, &client("a"))), 503);
+        assert_eq!(status(next_sequence_response(&store, "retry", &endpoint, &client("a"))), 200);
+        assert_eq!(status(next_sequence_response(&store, "retry", &endpoint, &client("b"))), 503);
+    }
+
+    #[test]
+    fn test_weighted_pick_skips_zero_weights() {
+        let entries: Vec<SequenceEntry> = serde_yaml::from_str(
+            r#"
+- { status: 500, body: null, weight: 0 }
+- { status: 200, body: null, weight: 3 }
+"#,
+        )
+        .unwrap();
+        let mut rng = rand::thread_rng();
+        assert!((0..50).all(|_| pick_weighted(&entries, &mut rng) == 1));
+    }
+}
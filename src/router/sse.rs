@@ -0,0 +1,131 @@
+use axum::response::{
+    sse::{Event, KeepAlive, Sse},
+    IntoResponse, Response,
+};
+use futures::stream::{self, Stream};
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{SseConfig, SseEvent, SseKeepAlive, SseRepeat};
+use crate::middleware::apply_delay;
+use crate::request::RequestContext;
+use crate::response::generate_response_body;
+
+/// Streams an `sse:` response. Each event is rendered just before it is
+/// sent, after its delay, so `$fake` values differ between rounds.
+pub fn sse_response(sse: &SseConfig, ctx: &RequestContext) -> Response {
+    let mut response = Sse::new(event_stream(Arc::new(sse.clone()), Arc::new(ctx.clone())));
+    if let Some(keep_alive) = &sse.keep_alive {
+        let (interval, text) = match keep_alive {
+            SseKeepAlive::Interval(interval) => (*interval, ""),
+            SseKeepAlive::Comment { interval, text } => (*interval, text.as_str()),
+        };
+        response = response.keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_millis(interval))
+                .text(single_line(text)),
+        );
+    }
+    response.into_response()
+}
+
+fn event_stream(
+    sse: Arc<SseConfig>,
+    ctx: Arc<RequestContext>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    // The state is the next event's index and how many rounds are done
+    stream::unfold((0, 0), move |(index, round)| {
+        let sse = sse.clone();
+        let ctx = ctx.clone();
+        async move {
+            if let SseRepeat::Times(times) = sse.repeat {
+                if round >= times {
+                    return None;
+                }
+            }
+            let event = sse.events.get(index)?;
+            if let Some(delay) = &event.delay {
+                apply_delay(delay, &ctx.rng).await;
+            }
+
+            let next = if index + 1 < sse.events.len() {
+                (index + 1, round)
+            } else {
+                (0, round + 1)
+            };
+            Some((Ok(render_event(event, &ctx)), next))
+        }
+    })
+}
+
+fn render_event(event: &SseEvent, ctx: &RequestContext) -> Event {
+    let mut rendered = Event::default();
+    if let Some(name) = &event.event {
+        rendered = rendered.event(single_line(&name.render(ctx)));
+    }
+    if let Some(id) = &event.id {
+        rendered = rendered.id(single_line(&id.render(ctx)).replace('\0', ""));
+    }
+    let data = match generate_response_body(&event.data, ctx) {
+        Value::String(text) => text.replace('\r', ""),
+        value => value.to_string(),
+    };
+    rendered.data(data)
+}
+
+/// SSE field values cannot span lines; keep the first one. A lone `\r`
+/// ends a line too.
+fn single_line(value: &str) -> String {
+    value.split(['\r', '\n']).next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_events_are_streamed_and_repeated() {
+        let sse: SseConfig = serde_yaml::from_str(
+            r#"
+events:
+  - event: token
+    id: "{{params.id}}"
+    data: "Hello"
+  - data: { done: true }
+    delay: 5
+repeat: 2
+"#,
+        )
+        .unwrap();
+        let mut ctx = RequestContext::default();
+        ctx.params.insert("id".to_string(), "7".to_string());
+
+        let response = sse_response(&sse, &ctx);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let round = "event: token\nid: 7\ndata: Hello\n\ndata: {\"done\":true}\n\n";
+        assert_eq!(std::str::from_utf8(&body).unwrap(), round.repeat(2));
+    }
+
+    #[test]
+    fn test_names_and_ids_from_request_data_stay_on_one_line() {
+        let event: SseEvent = serde_yaml::from_str(
+            r#"
+event: "{{query.kind}}"
+id: "{{query.id}}"
+data: "Hello"
+"#,
+        )
+        .unwrap();
+        let ctx = RequestContext {
+            query: crate::request::parse_query("kind=a%0Db&id=1%002%0D3"),
+            ..Default::default()
+        };
+
+        // Would panic if a line break or NUL reached the event
+        let _ = render_event(&event, &ctx);
+        assert_eq!(single_line("a\rb\nc"), "a");
+    }
+}
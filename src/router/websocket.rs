@@ -0,0 +1,200 @@
+use axum::{
+    body::Bytes,
+    extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+};
+use serde_json::Value;
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+
+use crate::config::{WebSocketClose, WebSocketConfig};
+use crate::middleware::apply_delay;
+use crate::request::RequestContext;
+use crate::response::{generate_response_body, BodyTemplate};
+
+use super::check_condition;
+
+/// Accepts a WebSocket upgrade and runs the endpoint's script on the
+/// connection. `ctx` is the upgrade request; templates can use its params,
+/// query and headers.
+pub fn websocket_response(
+    upgrade: WebSocketUpgrade,
+    script: &WebSocketConfig,
+    ctx: &RequestContext,
+) -> Response {
+    let script = script.clone();
+    let ctx = ctx.clone();
+    upgrade.on_upgrade(move |socket| run_script(socket, script, ctx))
+}
+
+async fn run_script(mut socket: WebSocket, script: WebSocketConfig, ctx: RequestContext) {
+    for message in &script.on_connect {
+        if socket.send(render_message(message, &ctx)).await.is_err() {
+            return;
+        }
+    }
+
+    let start = Instant::now();
+    let close_at = script
+        .close
+        .as_ref()
+        .and_then(|close| close.after)
+        .map(|ms| start + Duration::from_millis(ms));
+    let mut next_ticks: Vec<Instant> = script
+        .periodic
+        .iter()
+        .map(|periodic| start + Duration::from_millis(periodic.interval))
+        .collect();
+
+    loop {
+        let next_tick = next_ticks
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by_key(|(_, at)| *at);
+
+        tokio::select! {
+            incoming = socket.recv() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Binary(bytes))) => match String::from_utf8(bytes) {
+                        Ok(text) => text,
+                        Err(_) => continue,
+                    },
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                };
+
+                let message_ctx = message_context(&ctx, text);
+                let Some(reply) = script
+                    .replies
+                    .iter()
+                    .find(|reply| check_condition(&reply.condition, &message_ctx))
+                else {
+                    continue;
+                };
+                if let Some(delay) = &reply.delay {
+                    apply_delay(delay, &message_ctx.rng).await;
+                }
+                for message in &reply.send {
+                    if socket.send(render_message(message, &message_ctx)).await.is_err() {
+                        return;
+                    }
+                }
+                if let Some(close) = &reply.close {
+                    if let Some(ms) = close.after {
+                        sleep(Duration::from_millis(ms)).await;
+                    }
+                    close_socket(socket, close).await;
+                    return;
+                }
+            }
+            _ = wait_until(next_tick.map(|(_, at)| at)) => {
+                if let Some((index, at)) = next_tick {
+                    let periodic = &script.periodic[index];
+                    next_ticks[index] = at + Duration::from_millis(periodic.interval);
+                    if socket.send(render_message(&periodic.send, &ctx)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            _ = wait_until(close_at) => {
+                if let Some(close) = &script.close {
+                    close_socket(socket, close).await;
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// The upgrade request with an incoming message as its body: parsed as JSON
+/// when it is JSON, a string otherwise.
+fn message_context(ctx: &RequestContext, text: String) -> RequestContext {
+    let mut message_ctx = ctx.clone();
+    message_ctx.body = Some(serde_json::from_str(&text).unwrap_or(Value::String(text.clone())));
+    message_ctx.raw_body = Bytes::from(text);
+    message_ctx
+}
+
+fn render_message(message: &BodyTemplate, ctx: &RequestContext) -> Message {
+    match generate_response_body(message, ctx) {
+        Value::String(text) => Message::Text(text),
+        value => Message::Text(value.to_string()),
+    }
+}
+
+async fn close_socket(mut socket: WebSocket, close: &WebSocketClose) {
+    let frame = CloseFrame {
+        code: close.code,
+        reason: close.reason.clone().into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+async fn wait_until(at: Option<Instant>) {
+    match at {
+        Some(at) => sleep_until(at).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    #[tokio::test]
+    async fn test_script_greets_replies_and_closes() {
+        let script: WebSocketConfig = serde_yaml::from_str(
+            r#"
+on_connect:
+  - { type: "welcome", room: "{{params.room}}" }
+replies:
+  - if: { body_field: "type", equals: "ping" }
+    send:
+      - { type: "pong", seq: "{{body.seq}}" }
+  - if: { body_field: "$", equals: "bye" }
+    send: ["see you"]
+    close: { code: 4001, reason: "done" }
+"#,
+        )
+        .unwrap();
+        let mut ctx = RequestContext::default();
+        ctx.params.insert("room".to_string(), "lobby".to_string());
+
+        let app = Router::new().route(
+            "/ws",
+            get(move |upgrade: WebSocketUpgrade| async move {
+                websocket_response(upgrade, &script, &ctx)
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        let welcome = client.next().await.unwrap().unwrap();
+        assert_eq!(welcome.into_text().unwrap(), r#"{"room":"lobby","type":"welcome"}"#);
+
+        client
+            .send(ClientMessage::Text(r#"{"type":"ping","seq":3}"#.into()))
+            .await
+            .unwrap();
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply.into_text().unwrap(), r#"{"seq":3,"type":"pong"}"#);
+
+        client.send(ClientMessage::Text("bye".into())).await.unwrap();
+        assert_eq!(client.next().await.unwrap().unwrap().into_text().unwrap(), "see you");
+        match client.next().await.unwrap().unwrap() {
+            ClientMessage::Close(Some(frame)) => {
+                assert_eq!(u16::from(frame.code), 4001);
+                assert_eq!(frame.reason, "done");
+            }
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use parking_lot::Mutex;
+use serde_json::{json, Map, Value};
+use std::collections::VecDeque;
+
+use crate::request::RequestContext;
+
+/// The most recent requests the mock handled, oldest first.
+#[derive(Default)]
+pub struct Journal {
+    inner: Mutex<JournalInner>,
+}
+
+#[derive(Default)]
+struct JournalInner {
+    entries: VecDeque<JournalEntry>,
+    next_id: u64,
+}
+
+#[derive(Clone)]
+pub struct JournalEntry {
+    pub id: u64,
+    pub timestamp: String,
+    pub request: RequestContext,
+    /// The endpoint that answered, or `None` for unmatched requests.
+    pub endpoint: Option<String>,
+}
+
+impl Journal {
+    /// Records a request, dropping the oldest entries beyond `limit`.
+    pub fn record(&self, request: &RequestContext, endpoint: Option<String>, limit: usize) {
+        if limit == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock();
+        inner.next_id += 1;
+        let entry = JournalEntry {
+            id: inner.next_id,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            request: request.clone(),
+            endpoint,
+        };
+        inner.entries.push_back(entry);
+        while inner.entries.len() > limit {
+            inner.entries.pop_front();
+        }
+    }
+
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.inner.lock().entries.iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.inner.lock().entries.clear();
+    }
+}
+
+impl JournalEntry {
+    pub fn matched(&self) -> bool {
+        self.endpoint.is_some()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut headers = Map::new();
+        for name in self.request.headers.keys() {
+            headers.insert(name.to_string(), json!(self.request.header_values(name.as_str())));
+        }
+
+        json!({
+            "id": self.id,
+            "timestamp": self.timestamp,
+            "method": self.request.method,
+            "path": self.request.path,
+            "query": self.request.query,
+            "headers": headers,
+            "body": self.request.body,
+            "matched": self.matched(),
+            "endpoint": self.endpoint
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_is_bounded() {
+        let journal = Journal::default();
+        for path in ["/a", "/b", "/c"] {
+            let request = RequestContext {
+                path: path.to_string(),
+                ..Default::default()
+            };
+            journal.record(&request, None, 2);
+        }
+
+        let entries = journal.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].request.path, "/b");
+        assert_eq!(entries[1].id, 3);
+        assert!(!entries[1].matched());
+    }
+}
@@ -0,0 +1,46 @@
+mod journal;
+mod resources;
+mod scenarios;
+mod seeds;
+mod sequences;
+
+pub use journal::*;
+pub use resources::*;
+pub use scenarios::*;
+pub use seeds::*;
+pub use sequences::*;
+
+use std::sync::Arc;
+
+use crate::config::ConfigManager;
+
+/// Shared state handed to every request handler: the live config plus the
+/// runtime data mocks accumulate while the server runs.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<ConfigManager>,
+    pub journal: Arc<Journal>,
+    /// Client for `proxy:` upstreams, shared so connections are reused.
+    pub http_client: reqwest::Client,
+    pub resources: Arc<ResourceStore>,
+    pub scenarios: Arc<ScenarioStore>,
+    pub seeds: Arc<SeedStreams>,
+    pub sequences: Arc<SequenceStore>,
+}
+
+impl AppState {
+    pub fn new(config: Arc<ConfigManager>) -> Self {
+        Self {
+            config,
+            journal: Arc::new(Journal::default()),
+            http_client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .expect("HTTP client settings are valid"),
+            resources: Arc::new(ResourceStore::default()),
+            scenarios: Arc::new(ScenarioStore::default()),
+            seeds: Arc::new(SeedStreams::default()),
+            sequences: Arc::new(SequenceStore::default()),
+        }
+    }
+}
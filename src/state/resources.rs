@@ -0,0 +1,362 @@
+use parking_lot::Mutex;
+use rand::Rng;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::config::ResourceConfig;
+use crate::request::{RequestContext, RequestRng};
+use crate::response::{generate_response_body, value_to_string, ValuePath};
+
+/// In-memory collections behind `resource:` endpoints, keyed by the
+/// collection's base path.
+#[derive(Default)]
+pub struct ResourceStore {
+    collections: Mutex<HashMap<String, Collection>>,
+}
+
+pub struct Collection {
+    /// The resource config the collection was seeded from. When it changes
+    /// (e.g. after a config reload) the collection is seeded again.
+    fingerprint: String,
+    id_field: String,
+    items: Vec<Value>,
+    /// Draws generated UUID ids. Seeded once from the seeding request, so it
+    /// keeps advancing even when every request is seeded alike.
+    ids: RequestRng,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResourceError {
+    NotFound,
+    Conflict(String),
+    BadRequest(String),
+    MethodNotAllowed,
+}
+
+/// A page of list results plus the number of items that matched the filters.
+pub struct ListPage {
+    pub items: Vec<Value>,
+    pub total: usize,
+}
+
+impl ResourceStore {
+    /// Runs `f` on the collection stored at `base_path`, seeding it first if
+    /// it does not exist yet or its config changed. Fake values in the seed
+    /// come from `rng`.
+    pub fn with_collection<R>(
+        &self,
+        base_path: &str,
+        config: &ResourceConfig,
+        rng: &RequestRng,
+        f: impl FnOnce(&mut Collection) -> R,
+    ) -> R {
+        let fingerprint = serde_json::to_string(config).unwrap_or_default();
+        let mut collections = self.collections.lock();
+
+        let stale = collections
+            .get(base_path)
+            .is_none_or(|c| c.fingerprint != fingerprint);
+        if stale {
+            tracing::debug!("Seeding resource collection {}", base_path);
+            collections.insert(base_path.to_string(), Collection::seeded(config, fingerprint, rng));
+        }
+
+        f(collections.get_mut(base_path).expect("collection was just inserted"))
+    }
+}
+
+impl Collection {
+    fn seeded(config: &ResourceConfig, fingerprint: String, rng: &RequestRng) -> Self {
+        let mut collection = Self {
+            fingerprint,
+            id_field: config.id_field.clone(),
+            items: Vec::new(),
+            ids: RequestRng::seeded(rng.with(|rng| rng.gen())),
+        };
+
+        let ctx = RequestContext {
+            rng: rng.clone(),
+            ..Default::default()
+        };
+        let seed = match generate_response_body(&config.seed, &ctx) {
+            Value::Array(items) => items,
+            Value::Null => Vec::new(),
+            single => vec![single],
+        };
+        for item in seed {
+            if let Err(e) = collection.create(item) {
+                tracing::warn!("Skipping seed item: {:?}", e);
+            }
+        }
+
+        collection
+    }
+
+    /// Lists items. Query keys without a leading underscore filter on item
+    /// fields (dotted paths allowed); `_sort`/`_order` sort and
+    /// `_page`/`_limit` paginate.
+    pub fn list(&self, query: &HashMap<String, Vec<String>>) -> ListPage {
+        // Paths are parsed once per request; one that does not parse
+        // matches no item
+        let filters: Vec<(Option<ValuePath>, &Vec<String>)> = query
+            .iter()
+            .filter(|(key, _)| !key.starts_with('_'))
+            .map(|(key, accepted)| (ValuePath::parse(key).ok(), accepted))
+            .collect();
+        let mut items: Vec<&Value> = self
+            .items
+            .iter()
+            .filter(|item| {
+                filters.iter().all(|(path, accepted)| {
+                    path.as_ref()
+                        .and_then(|path| path.resolve(item))
+                        .map(|v| accepted.contains(&value_to_string(&v)))
+                        .unwrap_or(false)
+                })
+            })
+            .collect();
+
+        if let Some(sort_field) = first(query, "_sort") {
+            if let Ok(path) = ValuePath::parse(sort_field) {
+                items.sort_by(|a, b| {
+                    compare_values(path.resolve(a).as_ref(), path.resolve(b).as_ref())
+                });
+            }
+            if first(query, "_order").is_some_and(|o| o.eq_ignore_ascii_case("desc")) {
+                items.reverse();
+            }
+        }
+
+        let total = items.len();
+        if let Some(limit) = first(query, "_limit").and_then(|l| l.parse::<usize>().ok()) {
+            let page = first(query, "_page")
+                .and_then(|p| p.parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1);
+            items = items
+                .into_iter()
+                .skip((page - 1).saturating_mul(limit))
+                .take(limit)
+                .collect();
+        }
+
+        ListPage {
+            items: items.into_iter().cloned().collect(),
+            total,
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Result<Value, ResourceError> {
+        self.position(id)
+            .map(|index| self.items[index].clone())
+            .ok_or(ResourceError::NotFound)
+    }
+
+    /// Adds an item, generating an id when it has none: the next integer if
+    /// the existing ids are numeric, a UUID otherwise.
+    pub fn create(&mut self, item: Value) -> Result<Value, ResourceError> {
+        let Value::Object(mut fields) = item else {
+            return Err(ResourceError::BadRequest("item must be a JSON object".to_string()));
+        };
+
+        let id = match fields.get(&self.id_field) {
+            Some(Value::Null) | None => {
+                let id = self.next_id();
+                fields.insert(self.id_field.clone(), id.clone());
+                id
+            }
+            Some(id) => id.clone(),
+        };
+
+        let id_str = value_to_string(&id);
+        if self.position(&id_str).is_some() {
+            return Err(ResourceError::Conflict(format!(
+                "an item with {} \"{}\" already exists",
+                self.id_field, id_str
+            )));
+        }
+
+        let item = Value::Object(fields);
+        self.items.push(item.clone());
+        Ok(item)
+    }
+
+    /// Replaces an item. The id always comes from the path.
+    pub fn replace(&mut self, id: &str, item: Value) -> Result<Value, ResourceError> {
+        let index = self.position(id).ok_or(ResourceError::NotFound)?;
+        let Value::Object(mut fields) = item else {
+            return Err(ResourceError::BadRequest("item must be a JSON object".to_string()));
+        };
+
+        let current_id = self.items[index][&self.id_field].clone();
+        fields.insert(self.id_field.clone(), current_id);
+        self.items[index] = Value::Object(fields);
+        Ok(self.items[index].clone())
+    }
+
+    /// Applies a JSON Merge Patch (RFC 7396) to an item.
+    pub fn patch(&mut self, id: &str, patch: Value) -> Result<Value, ResourceError> {
+        let index = self.position(id).ok_or(ResourceError::NotFound)?;
+        if !patch.is_object() {
+            return Err(ResourceError::BadRequest("patch must be a JSON object".to_string()));
+        }
+
+        let current_id = self.items[index][&self.id_field].clone();
+        merge_patch(&mut self.items[index], &patch);
+        self.items[index][&self.id_field] = current_id;
+        Ok(self.items[index].clone())
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<Value, ResourceError> {
+        let index = self.position(id).ok_or(ResourceError::NotFound)?;
+        Ok(self.items.remove(index))
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.items.iter().position(|item| {
+            item.get(&self.id_field)
+                .is_some_and(|item_id| value_to_string(item_id) == id)
+        })
+    }
+
+    fn next_id(&self) -> Value {
+        let ids: Option<Vec<i64>> = self
+            .items
+            .iter()
+            .map(|item| item.get(&self.id_field).and_then(Value::as_i64))
+            .collect();
+        match ids {
+            Some(ids) => Value::from(ids.into_iter().max().unwrap_or(0) + 1),
+            None => Value::String(self.ids.uuid().to_string()),
+        }
+    }
+}
+
+fn first<'a>(query: &'a HashMap<String, Vec<String>>, key: &str) -> Option<&'a str> {
+    query.get(key).and_then(|v| v.first()).map(String::as_str)
+}
+
+fn compare_values(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(a), Some(b)) => value_to_string(a).cmp(&value_to_string(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_fields) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target_fields) = target else {
+        return;
+    };
+    for (key, value) in patch_fields {
+        if value.is_null() {
+            target_fields.remove(key);
+        } else {
+            merge_patch(target_fields.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(yaml: &str) -> ResourceConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_seed_and_crud() {
+        let store = ResourceStore::default();
+        let users = config(
+            r#"
+seed:
+  - { id: 1, name: "Ana", role: "admin" }
+  - { id: 2, name: "Bruno", role: "user" }
+"#,
+        );
+
+        let rng = RequestRng::default();
+        store.with_collection("/users", &users, &rng, |c| {
+            assert_eq!(c.get("2").unwrap()["name"], "Bruno");
+
+            let created = c.create(json!({ "name": "Carla", "role": "user" })).unwrap();
+            assert_eq!(created["id"], 3);
+            assert!(matches!(
+                c.create(json!({ "id": 1, "name": "Dup" })),
+                Err(ResourceError::Conflict(_))
+            ));
+
+            let patched = c.patch("3", json!({ "role": null, "team": "core" })).unwrap();
+            assert_eq!(patched, json!({ "id": 3, "name": "Carla", "team": "core" }));
+
+            let replaced = c.replace("1", json!({ "name": "Ana Maria" })).unwrap();
+            assert_eq!(replaced, json!({ "id": 1, "name": "Ana Maria" }));
+
+            c.delete("2").unwrap();
+            assert_eq!(c.get("2"), Err(ResourceError::NotFound));
+        });
+
+        // State survives between calls
+        store.with_collection("/users", &users, &rng, |c| {
+            assert_eq!(c.list(&HashMap::new()).total, 2);
+        });
+    }
+
+    #[test]
+    fn test_generated_seed_gets_ids() {
+        let store = ResourceStore::default();
+        let products = config(
+            r#"
+seed:
+  $array:
+    count: 3
+    template: { name: { $fake: "word" } }
+"#,
+        );
+
+        store.with_collection("/products", &products, &RequestRng::default(), |c| {
+            let page = c.list(&HashMap::new());
+            assert_eq!(page.total, 3);
+            assert!(page.items.iter().all(|item| item["id"].is_i64()));
+        });
+    }
+
+    #[test]
+    fn test_list_filters_sorts_and_paginates() {
+        let store = ResourceStore::default();
+        let items = config(
+            r#"
+seed:
+  - { id: 1, type: "book", price: 30 }
+  - { id: 2, type: "game", price: 10 }
+  - { id: 3, type: "book", price: 20 }
+  - { id: 4, type: "book", price: 5 }
+"#,
+        );
+        let query = crate::request::parse_query("type=book&_sort=price&_order=desc&_page=2&_limit=2");
+
+        store.with_collection("/items", &items, &RequestRng::default(), |c| {
+            let page = c.list(&query);
+            assert_eq!(page.total, 3);
+            assert_eq!(page.items, vec![json!({ "id": 4, "type": "book", "price": 5 })]);
+
+            let huge = crate::request::parse_query(&format!("_page={}&_limit={}", usize::MAX, usize::MAX));
+            assert!(c.list(&huge).items.is_empty());
+        });
+    }
+}
@@ -0,0 +1,81 @@
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::ScenarioConfig;
+
+/// Current state of every scenario. A scenario that has not moved yet (or
+/// was reset) is in its configured initial state.
+#[derive(Default)]
+pub struct ScenarioStore {
+    states: Mutex<HashMap<String, String>>,
+}
+
+impl ScenarioStore {
+    pub fn state(&self, name: &str, scenario: &ScenarioConfig) -> String {
+        self.states
+            .lock()
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| scenario.initial.clone())
+    }
+
+    /// Moves a scenario to `to`, but only if it is still in `from` (any state
+    /// when `from` is unset). Returns whether the transition happened.
+    pub fn advance(
+        &self,
+        name: &str,
+        scenario: &ScenarioConfig,
+        from: Option<&str>,
+        to: &str,
+    ) -> bool {
+        let mut states = self.states.lock();
+        let current = states.get(name).unwrap_or(&scenario.initial);
+        if from.is_some_and(|from| from != current) {
+            return false;
+        }
+        states.insert(name.to_string(), to.to_string());
+        true
+    }
+
+    pub fn set(&self, name: &str, state: &str) {
+        self.states.lock().insert(name.to_string(), state.to_string());
+    }
+
+    pub fn reset(&self, name: &str) {
+        self.states.lock().remove(name);
+    }
+
+    pub fn reset_all(&self) {
+        self.states.lock().clear();
+    }
+
+    /// The state of every configured scenario.
+    pub fn snapshot(&self, scenarios: &BTreeMap<String, ScenarioConfig>) -> BTreeMap<String, String> {
+        scenarios
+            .iter()
+            .map(|(name, scenario)| (name.clone(), self.state(name, scenario)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance_and_reset() {
+        let store = ScenarioStore::default();
+        let checkout = ScenarioConfig {
+            initial: "cart".to_string(),
+            states: Vec::new(),
+        };
+
+        assert_eq!(store.state("checkout", &checkout), "cart");
+        assert!(!store.advance("checkout", &checkout, Some("paid"), "shipped"));
+        assert!(store.advance("checkout", &checkout, Some("cart"), "paid"));
+        assert_eq!(store.state("checkout", &checkout), "paid");
+
+        store.reset("checkout");
+        assert_eq!(store.state("checkout", &checkout), "cart");
+    }
+}
@@ -0,0 +1,23 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+use crate::request::RequestRng;
+
+/// The seeded generators of this run, one per seed and route. Each is
+/// seeded once and then shared by every request it applies to, so a
+/// restarted server replays the same values in the same order while
+/// consecutive requests still get different ones.
+#[derive(Default)]
+pub struct SeedStreams {
+    streams: Mutex<HashMap<u64, RequestRng>>,
+}
+
+impl SeedStreams {
+    pub fn stream(&self, seed: u64) -> RequestRng {
+        self.streams
+            .lock()
+            .entry(seed)
+            .or_insert_with(|| RequestRng::seeded(seed))
+            .clone()
+    }
+}
@@ -0,0 +1,31 @@
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+
+/// How many times each response sequence has been served, keyed by endpoint
+/// and, for per-client sequences, by client.
+#[derive(Default)]
+pub struct SequenceStore {
+    counters: Mutex<HashMap<String, usize>>,
+}
+
+impl SequenceStore {
+    /// Returns how many times `key` was served before and counts this call.
+    pub fn next(&self, key: &str) -> usize {
+        let mut counters = self.counters.lock();
+        let counter = counters.entry(key.to_string()).or_insert(0);
+        *counter += 1;
+        *counter - 1
+    }
+
+    pub fn reset(&self) {
+        self.counters.lock().clear();
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, usize> {
+        self.counters
+            .lock()
+            .iter()
+            .map(|(key, count)| (key.clone(), *count))
+            .collect()
+    }
+}